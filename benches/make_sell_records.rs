@@ -0,0 +1,39 @@
+//! Times computing the sell records of a synthetic account of 100,000 lots. Compare
+//! `cargo bench` with `cargo bench --features parallel`.
+
+extern crate capgains;
+extern crate chrono;
+
+use std::time::{Duration, Instant};
+
+use capgains::{testutil, Account, SellOptions, TaxRates};
+
+const LOTS: usize = 100_000;
+const RUNS: u32 = 10;
+const SEED: u64 = 1;
+
+fn main() {
+    let (records, fund_prices) = testutil::generate(LOTS, SEED);
+    let account = Account::new(records);
+    let tax_rates = TaxRates::flat(0.15);
+    let sale_date = chrono::NaiveDate::from_ymd(2018, 1, 2);
+    let options = SellOptions::default();
+
+    let mut make = Duration::default();
+    let mut ranked = Duration::default();
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let sell_records = account.make_sell_records(&fund_prices, &tax_rates, sale_date, &options).unwrap();
+        make += start.elapsed();
+        assert_eq!(sell_records.len(), LOTS);
+
+        let start = Instant::now();
+        account.ranked_sell_records(&fund_prices, &tax_rates, sale_date, &options).unwrap();
+        ranked += start.elapsed();
+    }
+
+    let parallel = if cfg!(feature = "parallel") { "parallel" } else { "serial" };
+    println!("{} lots, {}, mean of {} runs", LOTS, parallel, RUNS);
+    println!("  make_sell_records:   {:?}", make / RUNS);
+    println!("  ranked_sell_records: {:?}", ranked / RUNS);
+}
@@ -0,0 +1,52 @@
+//! Times computing the sell records of synthetic accounts of 100 to 100,000 lots and selling
+//! half of each with `minimum_cap_gains`, which sorts the records and partially sells the last
+//! one. Throughput is in lots per second, so the sizes can be compared.
+
+extern crate capgains;
+extern crate chrono;
+
+use std::time::{Duration, Instant};
+
+use capgains::{testutil, Account, SellOptions, TaxRates};
+
+const SIZES: [usize; 4] = [100, 1_000, 10_000, 100_000];
+/// Lots timed per size, so that the small accounts are run enough times to time.
+const LOTS_PER_SIZE: usize = 1_000_000;
+const SEED: u64 = 1;
+
+fn lots_per_sec(lots: usize, elapsed: Duration) -> f64 {
+    lots as f64 / elapsed.as_secs_f64()
+}
+
+fn main() {
+    let tax_rates = TaxRates::flat(0.15);
+    let sale_date = chrono::NaiveDate::from_ymd(2018, 1, 2);
+    let options = SellOptions::default();
+
+    let parallel = if cfg!(feature = "parallel") { "parallel" } else { "serial" };
+    println!("{}, lots per second", parallel);
+    println!("  {:>7}  {:>17}  {:>17}", "lots", "make_sell_records", "minimum_cap_gains");
+    for &lots in &SIZES {
+        let (records, fund_prices) = testutil::generate(lots, SEED);
+        let account = Account::new(records);
+        let sell_records = account.make_sell_records(&fund_prices, &tax_rates, sale_date, &options).unwrap();
+        let target = sell_records.iter().map(|s| s.net_amount).sum::<f64>() / 2.0;
+        let runs = (LOTS_PER_SIZE / lots).max(1);
+
+        let mut make = Duration::default();
+        let mut sell = Duration::default();
+        for _ in 0..runs {
+            let start = Instant::now();
+            let sell_records = account.make_sell_records(&fund_prices, &tax_rates, sale_date, &options).unwrap();
+            make += start.elapsed();
+            assert_eq!(sell_records.len(), lots);
+
+            let start = Instant::now();
+            account.minimum_cap_gains(&fund_prices, target, &tax_rates, sale_date, &options).unwrap();
+            sell += start.elapsed();
+        }
+
+        let timed = lots * runs;
+        println!("  {:>7}  {:>17.0}  {:>17.0}", lots, lots_per_sec(timed, make), lots_per_sec(timed, sell));
+    }
+}
@@ -0,0 +1,101 @@
+//! Default options read from a configuration file.
+//!
+//! The file uses a small subset of TOML: one `key = value` pair per line, where the value is a
+//! double-quoted string or a bare number, and `#` starts a comment. Tables are not supported.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::str::FromStr;
+
+use serde::de::value::{Error as ValueError, MapDeserializer};
+use serde::de::{self, Deserialize, Deserializer};
+
+use capgains::TaxRates;
+
+use Format;
+
+/// Config file read when `--config` is not given, if it exists.
+pub const DEFAULT_CONFIG: &str = "capgains.toml";
+
+/// Options read from a config file. Command line flags override them.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default, deserialize_with = "de_from_str")]
+    pub tax_rate: Option<TaxRates>,
+    #[serde(default, deserialize_with = "de_from_str")]
+    pub long_term_rate: Option<f64>,
+    #[serde(default, deserialize_with = "de_from_str")]
+    pub output_format: Option<Format>,
+    pub date_format: Option<String>,
+}
+
+impl Config {
+    /// Reads the config file `filename`, or `DEFAULT_CONFIG` if no file is given. A missing
+    /// default config file results in an empty config.
+    pub fn load(filename: Option<&str>) -> Result<Config, String> {
+        let path = filename.unwrap_or(DEFAULT_CONFIG);
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(ref err) if filename.is_none() && err.kind() == io::ErrorKind::NotFound => {
+                return Ok(Config::default())
+            }
+            Err(err) => return Err(format!("{}: {}", path, err)),
+        };
+        text.parse().map_err(|err| format!("{}: {}", path, err))
+    }
+}
+
+impl FromStr for Config {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pairs = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let pair = parse_line(line).map_err(|err| format!("line {}: {}", i + 1, err))?;
+            pairs.extend(pair);
+        }
+
+        let deserializer: MapDeserializer<_, ValueError> = MapDeserializer::new(pairs.into_iter());
+        Config::deserialize(deserializer).map_err(|err| err.to_string())
+    }
+}
+
+/// Parses a `key = value` line, returning `None` for a blank or comment line.
+fn parse_line(line: &str) -> Result<Option<(String, String)>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let i = line.find('=').ok_or_else(|| "expected <key> = <value>".to_string())?;
+    let key = line[..i].trim();
+    if key.is_empty() || key.starts_with('[') {
+        return Err("expected <key> = <value>".to_string());
+    }
+
+    let rest = line[i + 1..].trim();
+    let (value, rest) = if let Some(quoted) = rest.strip_prefix('"') {
+        let end = quoted.find('"').ok_or_else(|| "unterminated string".to_string())?;
+        (quoted[..end].to_string(), quoted[end + 1..].trim())
+    } else {
+        let end = rest.find('#').unwrap_or(rest.len());
+        (rest[..end].trim().to_string(), "")
+    };
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err(format!("unexpected '{}' after the value of '{}'", rest, key));
+    }
+
+    Ok(Some((key.to_string(), value)))
+}
+
+fn de_from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let s = String::deserialize(deserializer)?;
+    T::from_str(&s).map(Some).map_err(de::Error::custom)
+}
@@ -0,0 +1,404 @@
+//! A streaming gzip decoder, sufficient for reading compressed input files without an external
+//! `gzip` command. Concatenated gzip members are decoded one after another, like `gzip -d` does.
+
+use std::cmp;
+use std::io::{self, Read};
+use std::mem;
+
+/// Size of the DEFLATE window, the farthest back a match can refer to.
+const WINDOW: usize = 32 * 1024;
+/// Number of bytes decoded ahead of the reader at a time.
+const CHUNK: usize = 16 * 1024;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+/// Order in which the code lengths of the code length alphabet are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn format_violated() -> io::Error {
+    invalid("invalid compressed data--format violated")
+}
+
+/// A canonical Huffman code, stored as the number of codes of each length and the symbols in
+/// code order.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Builds the code of the symbols with the given code lengths, where 0 is an unused symbol.
+    /// Incomplete codes are allowed, e.g. for a single distance code, but an unused code is an
+    /// error once it's read.
+    fn new(lengths: &[u8]) -> io::Result<Self> {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err(format_violated());
+            }
+        }
+
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    /// The codes of a block compressed with fixed Huffman codes.
+    fn fixed() -> (Self, Self) {
+        let mut lengths = [8u8; 288];
+        for len in &mut lengths[144..256] {
+            *len = 9;
+        }
+        for len in &mut lengths[256..280] {
+            *len = 7;
+        }
+        let literals = Huffman::new(&lengths).expect("the fixed code is complete");
+        let distances = Huffman::new(&[5; 30]).expect("the fixed code is complete");
+        (literals, distances)
+    }
+}
+
+/// Decompresses the gzip data read from `R`.
+pub struct Decoder<R: Read> {
+    input: io::Bytes<io::BufReader<R>>,
+    bit_buf: u32,
+    bit_count: u32,
+    state: State,
+    last_block: bool,
+    members: usize,
+    /// Decoded data, of which the bytes before `pos` have been read. The last `WINDOW` bytes are
+    /// kept for the matches that refer back to them.
+    output: Vec<u8>,
+    pos: usize,
+    /// CRC-32 of the current member, before its final inversion.
+    crc: u32,
+    /// Number of bytes decoded from the current member.
+    size: u64,
+}
+
+enum State {
+    Header,
+    Block,
+    Stored(usize),
+    Codes(Huffman, Huffman),
+    Trailer,
+    Done,
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(input: R) -> Self {
+        Decoder {
+            input: io::BufReader::new(input).bytes(),
+            bit_buf: 0,
+            bit_count: 0,
+            state: State::Header,
+            last_block: false,
+            members: 0,
+            output: Vec::new(),
+            pos: 0,
+            crc: 0,
+            size: 0,
+        }
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        self.input.next().map_or(Ok(None), |byte| byte.map(Some))
+    }
+
+    fn byte(&mut self) -> io::Result<u8> {
+        self.next_byte()?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected end of file"))
+    }
+
+    fn u16_le(&mut self) -> io::Result<u16> {
+        Ok(u16::from(self.byte()?) | u16::from(self.byte()?) << 8)
+    }
+
+    fn u32_le(&mut self) -> io::Result<u32> {
+        Ok(u32::from(self.u16_le()?) | u32::from(self.u16_le()?) << 16)
+    }
+
+    /// Reads `n` bits, least significant first, as DEFLATE stores them.
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        while self.bit_count < n {
+            self.bit_buf |= u32::from(self.byte()?) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let bits = self.bit_buf & ((1 << n) - 1);
+        self.bit_buf >>= n;
+        self.bit_count -= n;
+        Ok(bits)
+    }
+
+    /// Skips to the next byte boundary. Bytes are only read as their bits are needed, so fewer
+    /// than 8 bits are ever left over.
+    fn align(&mut self) {
+        self.bit_buf = 0;
+        self.bit_count = 0;
+    }
+
+    /// Reads a symbol of `code`. Huffman codes are stored most significant bit first.
+    fn symbol(&mut self, code: &Huffman) -> io::Result<u16> {
+        let (mut bits, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &code.counts[1..] {
+            bits |= self.bits(1)? as i32;
+            let count = i32::from(count);
+            if bits - first < count {
+                return Ok(code.symbols[(index + bits - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            bits <<= 1;
+        }
+        Err(format_violated())
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.crc = CRC_TABLE[((self.crc ^ u32::from(byte)) & 0xff) as usize] ^ (self.crc >> 8);
+        self.size += 1;
+        self.output.push(byte);
+    }
+
+    /// Reads the header of the next member, or ends the stream at the end of the input after
+    /// the first member.
+    fn header(&mut self) -> io::Result<State> {
+        let magic = match self.next_byte()? {
+            None if self.members > 0 => return Ok(State::Done),
+            None => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected end of file")),
+            Some(byte) => [byte, self.byte()?],
+        };
+        if magic != [0x1f, 0x8b] {
+            return Err(invalid("not in gzip format"));
+        }
+        if self.byte()? != 8 {
+            return Err(invalid("unknown compression method"));
+        }
+        let flags = self.byte()?;
+        if flags & 0xe0 != 0 {
+            return Err(invalid("unknown flags"));
+        }
+        // modification time, extra flags and operating system
+        for _ in 0..6 {
+            self.byte()?;
+        }
+        if flags & FEXTRA != 0 {
+            for _ in 0..self.u16_le()? {
+                self.byte()?;
+            }
+        }
+        for &flag in &[FNAME, FCOMMENT] {
+            if flags & flag != 0 {
+                while self.byte()? != 0 {}
+            }
+        }
+        if flags & FHCRC != 0 {
+            self.u16_le()?;
+        }
+
+        self.members += 1;
+        self.last_block = false;
+        self.crc = !0;
+        self.size = 0;
+        Ok(State::Block)
+    }
+
+    fn block(&mut self) -> io::Result<State> {
+        if self.last_block {
+            return Ok(State::Trailer);
+        }
+        self.last_block = self.bits(1)? == 1;
+        match self.bits(2)? {
+            0 => {
+                self.align();
+                let len = self.u16_le()?;
+                if len != !self.u16_le()? {
+                    return Err(format_violated());
+                }
+                Ok(State::Stored(len as usize))
+            }
+            1 => {
+                let (literals, distances) = Huffman::fixed();
+                Ok(State::Codes(literals, distances))
+            }
+            2 => self.dynamic_codes(),
+            _ => Err(format_violated()),
+        }
+    }
+
+    fn dynamic_codes(&mut self) -> io::Result<State> {
+        let num_literals = self.bits(5)? as usize + 257;
+        let num_distances = self.bits(5)? as usize + 1;
+        let num_code_lengths = self.bits(4)? as usize + 4;
+        if num_literals > 286 || num_distances > 30 {
+            return Err(format_violated());
+        }
+
+        let mut code_lengths = [0u8; 19];
+        for &i in &CODE_LENGTH_ORDER[..num_code_lengths] {
+            code_lengths[i] = self.bits(3)? as u8;
+        }
+        let code_length_code = Huffman::new(&code_lengths)?;
+
+        let mut lengths = Vec::with_capacity(num_literals + num_distances);
+        while lengths.len() < num_literals + num_distances {
+            let (len, repeat) = match self.symbol(&code_length_code)? {
+                len @ 0..=15 => (len as u8, 1),
+                16 => match lengths.last() {
+                    Some(&prev) => (prev, 3 + self.bits(2)?),
+                    None => return Err(format_violated()),
+                },
+                17 => (0, 3 + self.bits(3)?),
+                _ => (0, 11 + self.bits(7)?),
+            };
+            if lengths.len() + repeat as usize > num_literals + num_distances {
+                return Err(format_violated());
+            }
+            lengths.extend((0..repeat).map(|_| len));
+        }
+        // a block without an end code could never end
+        if lengths[256] == 0 {
+            return Err(format_violated());
+        }
+
+        let literals = Huffman::new(&lengths[..num_literals])?;
+        let distances = Huffman::new(&lengths[num_literals..])?;
+        Ok(State::Codes(literals, distances))
+    }
+
+    fn stored(&mut self, remaining: usize) -> io::Result<State> {
+        let n = cmp::min(remaining, CHUNK);
+        for _ in 0..n {
+            let byte = self.byte()?;
+            self.push(byte);
+        }
+        Ok(if n == remaining {
+            State::Block
+        } else {
+            State::Stored(remaining - n)
+        })
+    }
+
+    /// Decodes the symbols of a compressed block until a chunk is decoded or the block ends.
+    fn codes(&mut self, literals: Huffman, distances: Huffman) -> io::Result<State> {
+        let start = self.output.len();
+        while self.output.len() - start < CHUNK {
+            let i = match self.symbol(&literals)? as usize {
+                literal @ 0..=255 => {
+                    self.push(literal as u8);
+                    continue;
+                }
+                256 => return Ok(State::Block),
+                symbol if symbol - 257 < LENGTH_BASE.len() => symbol - 257,
+                _ => return Err(format_violated()),
+            };
+            let len = LENGTH_BASE[i] as usize + self.bits(u32::from(LENGTH_EXTRA[i]))? as usize;
+            let i = self.symbol(&distances)? as usize;
+            if i >= DIST_BASE.len() {
+                return Err(format_violated());
+            }
+            let dist = DIST_BASE[i] as usize + self.bits(u32::from(DIST_EXTRA[i]))? as usize;
+            if dist as u64 > self.size {
+                return Err(format_violated());
+            }
+            // the copy can overlap the bytes it writes, e.g. to repeat a run
+            for _ in 0..len {
+                let byte = self.output[self.output.len() - dist];
+                self.push(byte);
+            }
+        }
+        Ok(State::Codes(literals, distances))
+    }
+
+    fn trailer(&mut self) -> io::Result<State> {
+        self.align();
+        if self.u32_le()? != !self.crc {
+            return Err(invalid("invalid compressed data--crc error"));
+        }
+        if self.u32_le()? != self.size as u32 {
+            return Err(invalid("invalid compressed data--length error"));
+        }
+        Ok(State::Header)
+    }
+
+    /// Decodes the next chunk of output, dropping the read bytes that are no longer in the window.
+    fn fill(&mut self) -> io::Result<()> {
+        let keep = cmp::min(self.output.len(), WINDOW);
+        let drop = self.output.len() - keep;
+        self.output.drain(..drop);
+        self.pos -= drop;
+
+        while self.output.len() - self.pos < CHUNK {
+            self.state = match mem::replace(&mut self.state, State::Done) {
+                State::Header => self.header()?,
+                State::Block => self.block()?,
+                State::Stored(remaining) => self.stored(remaining)?,
+                State::Codes(literals, distances) => self.codes(literals, distances)?,
+                State::Trailer => self.trailer()?,
+                State::Done => break,
+            };
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.output.len() {
+            self.fill()?;
+        }
+        let n = cmp::min(buf.len(), self.output.len() - self.pos);
+        buf[..n].copy_from_slice(&self.output[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// The CRC-32 of each byte value, for updating the checksum a byte at a time.
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { 0xedb8_8320 ^ (crc >> 1) } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
@@ -0,0 +1,3640 @@
+// serde_derive wraps its generated impls in a named const
+#![allow(non_local_definitions)]
+
+extern crate chrono;
+extern crate csv;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer};
+
+use money::Cents;
+
+pub mod gzip;
+pub mod json;
+#[macro_use]
+pub mod log;
+pub mod money;
+pub mod testutil;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Record {
+    #[serde(rename = "Date", deserialize_with = "de_date_from_str")]
+    pub date: chrono::NaiveDate,
+    #[serde(rename = "Fund")]
+    pub fund: String,
+    #[serde(rename = "Transaction type")]
+    pub transaction_type: String,
+    #[serde(rename = "Shares transacted", deserialize_with = "de_finite")]
+    pub num_shares: f64,
+    #[serde(rename = "Share price", deserialize_with = "de_usd_from_str")]
+    pub share_price: f64,
+    #[serde(rename = "Amount", deserialize_with = "de_cents_from_str")]
+    pub amount: Cents, // dependent field
+    /// Commission or other fee paid for the transaction. The column is optional and an empty fee
+    /// is zero. Fees paid on acquisitions add to the cost basis of the lot, and fees paid on
+    /// disposals are taken from their proceeds, which lowers the loss moved to replacement shares
+    /// by the wash-sale rule. Fees of the sale computed are `SellOptions::sale_fee`.
+    ///
+    /// The fee of a lot of several shares is spread evenly over its shares: each share has
+    /// `fee / num_shares` added to its basis, or taken from its proceeds for a disposal. Selling
+    /// part of a lot takes the same part of its fee with it, and the rest stays with the shares
+    /// still held.
+    #[serde(rename = "Fee", default, deserialize_with = "de_fee_from_str")]
+    pub fee: f64,
+    /// Index of the row the record was read from, counting from 1 after the header row, to find
+    /// the record in its file. Set by `load_account`.
+    #[serde(skip)]
+    pub source_row: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SellRecord<'a> {
+    pub date_purchased: chrono::NaiveDate,
+    pub fund: &'a str,
+    pub num_shares: f64,
+    pub share_price_purchased: f64,
+    pub share_price: f64,
+    /// The value of the shares at `share_price`, rounded to cents, less the sale fee.
+    pub amount: Cents,
+    /// Positive for a gain and negative for a loss. The gains of the shares over their cost basis,
+    /// rounded to cents, less the sale fee.
+    pub cap_gains: Cents,
+    pub cap_gains_ratio: f64,
+    /// Fee paid for selling the record, which the amount and capital gains are net of. See
+    /// `SellOptions::sale_fee`.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub sale_fee: Cents,
+    /// The amount after taxes on the capital gains of this record. This exceeds the amount for
+    /// a loss, as the loss offsets taxes on other gains. The net amounts sum to the net amount of
+    /// a sale unless its losses exceed its gains, since the taxes owed are never negative.
+    pub net_amount: f64,
+    /// The date the record is sold, up to which the holding period is counted.
+    pub sale_date: chrono::NaiveDate,
+    pub holding_days: i64,
+    pub is_long_term: bool,
+    /// The date the fund was acquired within `WASH_SALE_DAYS` of the sale, if this record is sold
+    /// at a loss. The wash-sale rule may then disallow the loss. Set by `Account::flag_wash_sales`.
+    pub wash_sale: Option<chrono::NaiveDate>,
+    /// Name of the account holding this record, when selling from a `Portfolio`.
+    pub account: Option<String>,
+    /// Whether the capital gains of this record are taxed. They are not for records of
+    /// tax-advantaged accounts.
+    pub taxable: bool,
+    /// Group of share classes of the same underlying fund the fund of this record is a class
+    /// of, if any. See `Account::with_fund_groups`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fund_group: Option<String>,
+    /// Row of the account file the record was read from. See `Record::source_row`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_row: Option<u64>,
+}
+
+/// Whether a fee is zero, to leave out fees that aren't paid.
+fn is_zero(fee: &Cents) -> bool {
+    *fee == Cents::ZERO
+}
+
+impl<'a> SellRecord<'a> {
+    /// Returns the compound annual growth rate of the record over its holding period, as a
+    /// fraction of its cost basis, e.g. 0.07 for 7% a year, to compare the performance of lots.
+    /// Returns `None` for a record sold on the day it was purchased, whose return can't be
+    /// annualized, or without a positive cost basis.
+    pub fn annualized_return(&self) -> Option<f64> {
+        // the capital gains ratio is of the amount, so the basis is the rest of the amount
+        let basis_ratio = 1.0 - self.cap_gains_ratio;
+        if self.holding_days <= 0 || basis_ratio.is_nan() || basis_ratio <= 0.0 {
+            return None;
+        }
+        Some(basis_ratio.recip().powf(DAYS_PER_YEAR / self.holding_days as f64) - 1.0)
+    }
+
+    /// Returns `num_shares` of the shares of this record as a record of their own, which pays the
+    /// whole sale fee. The net amount is left for the caller to update.
+    fn part(&self, num_shares: f64) -> Self {
+        let cap_gains = Cents::from_dollars((self.share_price - self.share_price_purchased)*num_shares);
+        SellRecord {
+            num_shares,
+            amount: Cents::from_dollars(self.share_price*num_shares) - self.sale_fee,
+            cap_gains: cap_gains - self.sale_fee,
+            ..self.clone()
+        }
+    }
+
+    /// Returns this record sold on `sale_date` instead, with its holding period and taxes on
+    /// that date.
+    fn sold_on(self, sale_date: chrono::NaiveDate, tax_rates: &TaxRates, options: &SellOptions) -> Self {
+        let holding_days = sale_date.signed_duration_since(self.date_purchased).num_days();
+        let mut srec = SellRecord {
+            sale_date,
+            holding_days,
+            is_long_term: holding_days > options.long_term_days.unwrap_or(LONG_TERM_DAYS),
+            ..self
+        };
+        srec.net_amount = srec.amount.to_dollars() - tax_rates.taxes(&srec);
+        srec
+    }
+}
+
+/// How `Account::split_across_years` spreads a sale over two tax years.
+#[derive(Clone, Debug)]
+pub struct YearLimit {
+    /// Largest capital gains sold this year.
+    pub cap_gains: f64,
+    /// Date the rest of the sale is sold on, in the next year.
+    pub next_sale_date: chrono::NaiveDate,
+}
+
+/// A sale spread over two tax years by `Account::split_across_years`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct YearSplit<'a> {
+    /// The sale of this year. Its remaining records are those left for the next year.
+    pub first: SellSummary<'a>,
+    /// The sale of the next year, if the sale of this year doesn't reach the target.
+    pub second: Option<SellSummary<'a>>,
+}
+
+/// The records of a sale with its totals.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SellSummary<'a> {
+    pub records: Vec<SellRecord<'a>>,
+    pub total_amount: f64,
+    /// The sum of the capital gains of the records, each rounded to cents. The taxes are on the
+    /// unrounded capital gains.
+    pub total_cap_gains: f64,
+    /// The part of `total_cap_gains` from long-term records.
+    pub long_term_cap_gains: f64,
+    /// The part of the carryover loss of the tax rates that offsets the capital gains.
+    pub carryover_applied: f64,
+    /// The taxes owed on the capital gains of the sale, which are never negative.
+    pub total_tax: f64,
+    /// The total amount after taxes.
+    pub net_amount: f64,
+    /// The records considered for the sale that are not sold, including the rest of a partially
+    /// sold record.
+    pub remaining: Vec<SellRecord<'a>>,
+}
+
+impl<'a> SellSummary<'a> {
+    /// Computes the totals of selling `records`. The amounts and capital gains are summed as
+    /// `Cents` and the taxes are rounded to cents, so the totals are exactly the sums of the
+    /// amounts shown for the records and don't drift with the number of records.
+    pub fn new(records: Vec<SellRecord<'a>>, tax_rates: &TaxRates) -> Self {
+        let total_amount: Cents = records.iter().map(|s| s.amount).sum();
+        let total_cap_gains: Cents = records.iter().map(|s| s.cap_gains).sum();
+        let long_term_cap_gains: Cents = records.iter().filter(|s| s.is_long_term).map(|s| s.cap_gains).sum();
+        let total_tax = Cents::from_dollars(taxes_owed(tax_rates.sale_taxes(&records)));
+        let carryover_applied = tax_rates.carryover_applied(&records);
+
+        SellSummary {
+            records,
+            total_amount: total_amount.to_dollars(),
+            total_cap_gains: total_cap_gains.to_dollars(),
+            long_term_cap_gains: long_term_cap_gains.to_dollars(),
+            carryover_applied,
+            total_tax: total_tax.to_dollars(),
+            net_amount: (total_amount - total_tax).to_dollars(),
+            remaining: Vec::new(),
+        }
+    }
+
+    /// Sets the records that are not sold.
+    pub fn with_remaining(mut self, remaining: Vec<SellRecord<'a>>) -> Self {
+        self.remaining = remaining;
+        self
+    }
+
+    /// Returns the largest difference of the total amount and capital gains from summing the
+    /// rounded amounts of the records one after another as `f64`s. A difference of more than
+    /// `PRECISION_TOLERANCE` means the sale is large enough for `f64` rounding to matter.
+    pub fn precision_drift(&self) -> f64 {
+        let amount = self.records.iter().fold(0.0, |sum, s| sum + s.amount.to_dollars()) - self.total_amount;
+        let cap_gains = self.records.iter().fold(0.0, |sum, s| sum + s.cap_gains.to_dollars()) - self.total_cap_gains;
+        amount.abs().max(cap_gains.abs())
+    }
+}
+
+/// Largest difference between the compensated and naive totals of a sale that is not worth a
+/// warning.
+pub const PRECISION_TOLERANCE: f64 = 0.01;
+
+/// Splits a sell record into one of its first `n` shares and one of the rest. Each pays the
+/// sale fee, as the rest pays its own when it's sold.
+fn split_record<'a>(srec: SellRecord<'a>, n: f64, tax_rates: &TaxRates) -> (SellRecord<'a>, SellRecord<'a>) {
+    let part = |num_shares: f64| {
+        let mut part = srec.part(num_shares);
+        part.net_amount = part.amount.to_dollars() - tax_rates.taxes(&part);
+        part
+    };
+    (part(n), part(srec.num_shares - n))
+}
+
+/// Average length of a year, including leap days, used to annualize returns.
+const DAYS_PER_YEAR: f64 = 365.25;
+
+/// Records held for more than this many days are considered long term, unless the sell options
+/// set another holding period.
+pub const LONG_TERM_DAYS: i64 = 365;
+
+/// Buying a fund within this many days before or after selling it at a loss is a wash sale.
+pub const WASH_SALE_DAYS: i64 = 30;
+
+#[derive(Clone, Debug, Deserialize)]
+struct FundPrice {
+    #[serde(rename = "Fund")]
+    fund: String,
+    #[serde(rename = "Share price", deserialize_with = "de_usd_from_str")]
+    share_price: f64,
+    /// The date the fund is sold at the price. The column is optional and an empty date is the
+    /// sale date.
+    #[serde(rename = "Date", default, deserialize_with = "de_optional_date_from_str")]
+    date: Option<chrono::NaiveDate>,
+    /// The time of day of the price, for funds such as ETFs with several prices a day. The column
+    /// is optional. See `PriceTime`.
+    #[serde(rename = "Time", default, deserialize_with = "de_optional_time_from_str")]
+    time: Option<chrono::NaiveTime>,
+}
+
+/// Which price of a fund with several prices a day, at different times in the Time column of
+/// the fund prices file, is read. A price without a time is the price of the whole day, read
+/// only if the fund has no price with a time.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PriceTime {
+    /// The earliest price of the day.
+    Open,
+    /// The latest price of the day.
+    #[default]
+    Close,
+    /// The latest price at or before a time. A fund with only later prices has no price.
+    At(chrono::NaiveTime),
+}
+
+impl PriceTime {
+    /// Returns the price selected from the times and prices of a fund, if any.
+    fn select(self, prices: &[(Option<chrono::NaiveTime>, f64)]) -> Option<f64> {
+        let mut timed: Vec<_> = prices.iter().filter_map(|&(time, price)| time.map(|time| (time, price))).collect();
+        if timed.is_empty() {
+            return prices.first().map(|&(_, price)| price);
+        }
+        timed.sort_by_key(|&(time, _)| time);
+        match self {
+            PriceTime::Open => timed.first().map(|&(_, price)| price),
+            PriceTime::Close => timed.last().map(|&(_, price)| price),
+            PriceTime::At(at) => timed.iter().rev().find(|&&(time, _)| time <= at).map(|&(_, price)| price),
+        }
+    }
+}
+
+impl FromStr for PriceTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(PriceTime::Open),
+            "close" => Ok(PriceTime::Close),
+            _ => parse_time(s).map(PriceTime::At),
+        }
+    }
+}
+
+/// Parses a time of day as HH:MM or HH:MM:SS.
+fn parse_time(s: &str) -> Result<chrono::NaiveTime, String> {
+    chrono::NaiveTime::parse_from_str(s.trim(), "%H:%M:%S")
+        .or_else(|_| chrono::NaiveTime::parse_from_str(s.trim(), "%H:%M"))
+        .map_err(|_| format!("invalid time '{}', expected open, close or a time as HH:MM or HH:MM:SS", s))
+}
+
+fn de_optional_time_from_str<'de, D>(deserializer: D) -> Result<Option<chrono::NaiveTime>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: &str = Deserialize::deserialize(deserializer)?;
+    if s.trim().is_empty() {
+        return Ok(None);
+    }
+    parse_time(s).map(Some).map_err(de::Error::custom)
+}
+
+/// A share price of a fund on a day, from a file of price history.
+#[derive(Clone, Debug, Deserialize)]
+struct HistoricalPrice {
+    #[serde(rename = "Fund")]
+    fund: String,
+    #[serde(rename = "Date", deserialize_with = "de_date_from_str")]
+    date: chrono::NaiveDate,
+    #[serde(rename = "Share price", deserialize_with = "de_usd_from_str")]
+    share_price: f64,
+}
+
+/// Another name of a fund, e.g. the name "Vanguard Total Stock" used in an account file for the
+/// ticker VTSAX used in the price file.
+#[derive(Clone, Debug, Deserialize)]
+struct FundAlias {
+    #[serde(rename = "Alias")]
+    alias: String,
+    #[serde(rename = "Fund")]
+    fund: String,
+}
+
+/// A stock split of a fund, multiplying the shares held before `date` by `ratio`, e.g. 2 for a 2:1
+/// split or 0.1 for a 1:10 reverse split.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Split {
+    #[serde(rename = "Fund")]
+    pub fund: String,
+    #[serde(rename = "Date", deserialize_with = "de_date_from_str")]
+    pub date: chrono::NaiveDate,
+    #[serde(rename = "Ratio", deserialize_with = "de_ratio_from_str")]
+    pub ratio: f64,
+}
+
+/// The shares held of a fund, or their value, as stated by a broker, to check the records of an
+/// account against. See `reconcile`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Balance {
+    #[serde(rename = "Fund")]
+    pub fund: String,
+    #[serde(rename = "Shares", default, deserialize_with = "de_optional_usd_from_str")]
+    pub shares: Option<f64>,
+    #[serde(rename = "Value", default, deserialize_with = "de_optional_usd_from_str")]
+    pub value: Option<f64>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct TaxBracket {
+    #[serde(rename = "Threshold", deserialize_with = "de_usd_from_str")]
+    threshold: f64,
+    #[serde(rename = "Rate")]
+    rate: f64,
+}
+
+/// Progressive tax brackets. Capital gains stack on top of ordinary income, so each dollar of
+/// gains is taxed at the marginal rate of the bracket the income including it falls in.
+#[derive(Clone, Debug)]
+pub struct TaxBrackets {
+    /// Ordinary income taxed before any capital gains.
+    pub income: f64,
+    /// Thresholds and the marginal rates of income above them, sorted by threshold. Income below
+    /// the first threshold is not taxed.
+    brackets: Vec<(f64, f64)>,
+}
+
+impl TaxBrackets {
+    pub fn new(income: f64, mut brackets: Vec<(f64, f64)>) -> Self {
+        brackets.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        TaxBrackets { income, brackets }
+    }
+
+    /// Returns the taxes on `income`.
+    fn income_taxes(&self, income: f64) -> f64 {
+        let mut taxes = 0.0;
+        for (i, &(threshold, rate)) in self.brackets.iter().enumerate() {
+            let top = self.brackets.get(i + 1).map_or(income, |b| b.0.min(income));
+            if top > threshold {
+                taxes += (top - threshold) * rate;
+            }
+        }
+        taxes
+    }
+
+    /// Returns the taxes on `cap_gains` on top of the ordinary income. This is negative for a
+    /// loss, which offsets taxes on other gains.
+    pub fn taxes(&self, cap_gains: f64) -> f64 {
+        self.income_taxes(self.income + cap_gains) - self.income_taxes(self.income)
+    }
+}
+
+/// Tax rates applied to short- and long-term capital gains.
+#[derive(Clone, Debug)]
+pub struct TaxRates {
+    pub short_term: f64,
+    pub long_term: f64,
+    /// Rates for specific funds, applied to both short- and long-term capital gains. Funds not
+    /// in the map use the short- and long-term rates.
+    pub per_fund: HashMap<String, f64>,
+    /// Brackets applied to the capital gains of funds without a per-fund rate, in place of the
+    /// short- and long-term rates.
+    pub brackets: Option<TaxBrackets>,
+    /// Capital losses carried over from earlier years, which offset the taxable capital gains of
+    /// a sale before taxes. They offset the gains taxed by brackets first, then the other gains
+    /// at their average rate. The offset never makes the gains negative.
+    pub carryover_loss: f64,
+}
+
+impl TaxRates {
+    /// Applies the same rate to both short- and long-term capital gains.
+    pub fn flat(rate: f64) -> Self {
+        TaxRates {
+            short_term: rate,
+            long_term: rate,
+            per_fund: HashMap::new(),
+            brackets: None,
+            carryover_loss: 0.0,
+        }
+    }
+
+    /// Checks that every rate, including those of the brackets, is between 0 and 1.
+    pub fn validate(&self) -> Result<(), AccountError> {
+        let bracket_rates = self.brackets.iter().flat_map(|b| b.brackets.iter().map(|&(_, rate)| rate));
+        let term_rates = [self.short_term, self.long_term];
+        let mut rates = term_rates
+            .iter()
+            .cloned()
+            .chain(self.per_fund.values().cloned())
+            .chain(bracket_rates);
+        match rates.find(|rate| !(0.0..=1.0).contains(rate)) {
+            Some(rate) => Err(AccountError::InvalidTaxRate { rate }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.short_term == 0.0
+            && self.long_term == 0.0
+            && self.per_fund.values().all(|&r| r == 0.0)
+            && self.brackets.is_none()
+    }
+
+    /// Returns whether the capital gains of a sell record are taxed by the brackets.
+    fn uses_brackets(&self, srec: &SellRecord) -> bool {
+        srec.taxable && self.brackets.is_some() && !self.per_fund.contains_key(srec.fund)
+    }
+
+    /// Returns the rate applied to the capital gains of a sell record, which is zero if the record
+    /// is not taxable. For a record taxed by brackets, this is the average rate of selling the
+    /// record on its own.
+    pub fn rate(&self, srec: &SellRecord) -> f64 {
+        if self.uses_brackets(srec) {
+            let cap_gains = srec.cap_gains.to_dollars();
+            return if cap_gains == 0.0 { 0.0 } else { self.taxes(srec) / cap_gains };
+        }
+
+        if !srec.taxable {
+            0.0
+        } else if let Some(&rate) = self.per_fund.get(srec.fund) {
+            rate
+        } else if srec.is_long_term {
+            self.long_term
+        } else {
+            self.short_term
+        }
+    }
+
+    /// Returns the taxes due on the capital gains of a sell record sold on its own. This is
+    /// negative for a loss, which offsets the taxes due on the gains of other records sold.
+    pub fn taxes(&self, srec: &SellRecord) -> f64 {
+        match self.brackets {
+            Some(ref brackets) if self.uses_brackets(srec) => brackets.taxes(srec.cap_gains.to_dollars()),
+            _ => srec.cap_gains.to_dollars() * self.rate(srec),
+        }
+    }
+
+    /// Returns the sum of the taxes due on the capital gains of the records of a sale. With
+    /// brackets, this differs from summing `taxes` over the records, as the gains of each record
+    /// stack on those of the others.
+    pub fn sale_taxes(&self, sell_records: &[SellRecord]) -> f64 {
+        self.running_taxes(sell_records).total(self)
+    }
+
+    /// Returns the part of `carryover_loss` that offsets the capital gains of the records of a
+    /// sale.
+    pub fn carryover_applied(&self, sell_records: &[SellRecord]) -> f64 {
+        let taxes = self.running_taxes(sell_records);
+        self.carryover_loss.min(taxes.bracket_gains.max(0.0) + taxes.fixed_gains.max(0.0))
+    }
+
+    fn running_taxes(&self, sell_records: &[SellRecord]) -> SaleTaxes {
+        let mut taxes = SaleTaxes::default();
+        for srec in sell_records {
+            taxes.add(self, srec);
+        }
+        taxes
+    }
+}
+
+/// Running taxes of a sale as records are added to it.
+#[derive(Clone, Debug, Default)]
+struct SaleTaxes {
+    /// Taxes of the records taxed at a fixed rate.
+    fixed: f64,
+    /// Capital gains of the taxable records taxed at a fixed rate.
+    fixed_gains: f64,
+    /// Capital gains of the records taxed by brackets.
+    bracket_gains: f64,
+}
+
+impl SaleTaxes {
+    fn add(&mut self, tax_rates: &TaxRates, srec: &SellRecord) {
+        if tax_rates.uses_brackets(srec) {
+            self.bracket_gains += srec.cap_gains.to_dollars();
+        } else {
+            self.fixed += tax_rates.taxes(srec);
+            if srec.taxable {
+                self.fixed_gains += srec.cap_gains.to_dollars();
+            }
+        }
+    }
+
+    fn total(&self, tax_rates: &TaxRates) -> f64 {
+        let mut carryover = tax_rates.carryover_loss;
+        let bracket_taxes = match tax_rates.brackets {
+            Some(ref brackets) => {
+                let offset = carryover.min(self.bracket_gains.max(0.0));
+                carryover -= offset;
+                brackets.taxes(self.bracket_gains - offset)
+            }
+            None => 0.0,
+        };
+        let fixed_taxes = if carryover > 0.0 && self.fixed_gains > 0.0 {
+            self.fixed * (1.0 - carryover.min(self.fixed_gains) / self.fixed_gains)
+        } else {
+            self.fixed
+        };
+        fixed_taxes + bracket_taxes
+    }
+}
+
+/// Returns the taxes owed for a sale given the sum of the taxes of its records. Losses offset
+/// gains but never result in negative taxes.
+pub fn taxes_owed(taxes: f64) -> f64 {
+    taxes.max(0.0)
+}
+
+/// Parses either a single flat rate or a short-term and long-term rate separated by a comma.
+impl FromStr for TaxRates {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |rate: &str| f64::from_str(rate.trim()).map_err(|err| err.to_string());
+
+        let tax_rates = match s.find(',') {
+            Some(i) => TaxRates {
+                short_term: parse(&s[..i])?,
+                long_term: parse(&s[i + 1..])?,
+                per_fund: HashMap::new(),
+                brackets: None,
+                carryover_loss: 0.0,
+            },
+            None => TaxRates::flat(parse(s)?),
+        };
+        tax_rates.validate().map_err(|err| err.to_string())?;
+        Ok(tax_rates)
+    }
+}
+
+/// An error in the records of an account or in selling them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AccountError {
+    UnknownTransactionType {
+        transaction_type: String,
+        fund: String,
+        date: chrono::NaiveDate,
+    },
+    /// The records whose amount does not match share price * shares, one line per record.
+    MismatchedAmounts(Vec<String>),
+    MissingPrice { fund: String },
+    DuplicatePrice { fund: String, first: f64, second: f64 },
+    /// A fund listed in the fund prices file with different sale dates, where `None` is the sale
+    /// date of a row without a date.
+    DuplicateSaleDate {
+        fund: String,
+        first: Option<chrono::NaiveDate>,
+        second: Option<chrono::NaiveDate>,
+    },
+    /// A share price that is not positive, which leaves the capital gains ratio undefined.
+    InvalidPrice { fund: String, price: f64 },
+    InsufficientFunds { available: f64, target: f64 },
+    /// The target can't be reached by selling at most `max_lots` records.
+    InsufficientFundsInLots { max_lots: usize, available: f64, target: f64 },
+    InsufficientShares { fund: String, available: f64, target: f64 },
+    /// Fewer capital gains, or capital losses for a negative target, are held than the target.
+    InsufficientCapGains { available: f64, target: f64 },
+    InvalidDate { date: String, line: u64, format: String },
+    /// A sell target, of an amount or of shares, that is not positive.
+    InvalidTarget { target: f64 },
+    InvalidTaxRate { rate: f64 },
+    NoRecords { filename: String },
+    /// No held record of the fund was purchased on the date.
+    RecordNotFound { fund: String, date: chrono::NaiveDate },
+    /// An acquisition of negative shares, or a disposal of more shares than are held, which
+    /// opens a short position of `shares`, the shares missing to cover it.
+    ShortPosition {
+        fund: String,
+        date: chrono::NaiveDate,
+        shares: f64,
+    },
+    /// Stated balances that differ from the records by more than the tolerance.
+    UnreconciledBalances { count: usize },
+}
+
+impl fmt::Display for AccountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AccountError::UnknownTransactionType { ref transaction_type, ref fund, date } => {
+                write!(f, "Unknown transaction type '{}' for fund {} on {}", transaction_type, fund, date)
+            }
+            AccountError::MismatchedAmounts(ref records) => write!(
+                f,
+                "Amount does not match share price * shares for {} record(s):\n{}",
+                records.len(),
+                records.join("\n")
+            ),
+            AccountError::MissingPrice { ref fund } => write!(f, "Missing price for fund: {}", fund),
+            AccountError::DuplicatePrice { ref fund, first, second } => {
+                write!(f, "Duplicate prices for fund {}: {} and {}", fund, first, second)
+            }
+            AccountError::DuplicateSaleDate { ref fund, first, second } => {
+                let date = |date: Option<chrono::NaiveDate>| date.map_or("none".to_string(), |d| d.to_string());
+                write!(f, "Duplicate sale dates for fund {}: {} and {}", fund, date(first), date(second))
+            }
+            AccountError::InvalidPrice { ref fund, price } => {
+                write!(f, "Price for fund {} must be positive: {}", fund, price)
+            }
+            AccountError::InsufficientFunds { .. } => write!(f, "Insufficient funds."),
+            AccountError::InsufficientFundsInLots { max_lots, .. } => {
+                write!(f, "Insufficient funds to sell at most {} record(s).", max_lots)
+            }
+            AccountError::InsufficientShares { ref fund, .. } => {
+                write!(f, "Insufficient shares of fund: {}", fund)
+            }
+            AccountError::InsufficientCapGains { target, .. } => {
+                let kind = if target < 0.0 { "losses" } else { "gains" };
+                write!(f, "Insufficient capital {}.", kind)
+            }
+            AccountError::InvalidDate { ref date, line, ref format } => {
+                write!(f, "invalid date '{}' on line {}, expected the format: {}", date, line, format)
+            }
+            AccountError::InvalidTarget { target } => write!(f, "Sell target must be positive: {}", target),
+            AccountError::InvalidTaxRate { rate } => write!(f, "Tax rate must be between 0 and 1: {}", rate),
+            AccountError::NoRecords { ref filename } => write!(f, "No records loaded from {}", filename),
+            AccountError::RecordNotFound { ref fund, date } => {
+                write!(f, "No record of fund {} purchased on {} is held", fund, date)
+            }
+            AccountError::UnreconciledBalances { count } => {
+                write!(f, "{} stated balance(s) don't match the records", count)
+            }
+            AccountError::ShortPosition { ref fund, date, shares } => {
+                write!(f, "Short positions are not supported: fund {} on {} is short {} shares", fund, date, shares)
+            }
+        }
+    }
+}
+
+impl error::Error for AccountError {}
+
+#[derive(Debug)]
+pub enum Error {
+    Account(AccountError),
+    Csv(csv::Error),
+    Json(json::Error),
+    EmptyInput,
+    File(String, Box<Error>),
+    /// An error in the record with the given index, counting from 1 after the header row.
+    Record(u64, Box<Error>),
+}
+
+impl Error {
+    fn in_file(self, filename: &str) -> Self {
+        let filename = if filename == STDIN { "<stdin>" } else { filename };
+        Error::File(filename.to_string(), Box::new(self))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Account(ref err) => write!(f, "{}", err),
+            Error::Csv(ref err) => write!(f, "{}", err),
+            Error::Json(ref err) => write!(f, "{}", err),
+            Error::EmptyInput => write!(f, "No data to read"),
+            Error::File(ref filename, ref err) => write!(f, "{}: {}", filename, err),
+            Error::Record(index, ref err) => write!(f, "record {}: {}", index, err),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Account(ref err) => Some(err),
+            Error::Csv(ref err) => Some(err),
+            Error::Json(ref err) => Some(err),
+            Error::EmptyInput => None,
+            Error::File(_, ref err) | Error::Record(_, ref err) => Some(err.as_ref()),
+        }
+    }
+}
+
+impl From<AccountError> for Error {
+    fn from(err: AccountError) -> Self {
+        Error::Account(err)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(err: csv::Error) -> Self {
+        Error::Csv(err)
+    }
+}
+
+impl From<json::Error> for Error {
+    fn from(err: json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+fn de_date_from_str<'de, D>(deserializer: D) -> Result<chrono::NaiveDate, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: &str = Deserialize::deserialize(deserializer)?;
+    parse_date(s).map_err(de::Error::custom)
+}
+
+fn de_optional_date_from_str<'de, D>(deserializer: D) -> Result<Option<chrono::NaiveDate>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: &str = Deserialize::deserialize(deserializer)?;
+    if s.trim().is_empty() {
+        return Ok(None);
+    }
+    parse_date(s).map(Some).map_err(de::Error::custom)
+}
+
+/// Date formats accepted in account files. Two-digit years are tried before four-digit years,
+/// as "%Y" would otherwise accept "17" as the year 17.
+const DATE_FORMATS: &[&str] = &["%m/%d/%y", "%m/%d/%Y", "%Y-%m-%d", "%d-%b-%Y"];
+
+/// Parses a date in the first of `DATE_FORMATS` that matches.
+fn parse_date(s: &str) -> Result<chrono::NaiveDate, String> {
+    DATE_FORMATS
+        .iter()
+        .filter_map(|format| chrono::NaiveDate::parse_from_str(s.trim(), format).ok())
+        .next()
+        .ok_or_else(|| format!("invalid date '{}', expected one of the formats: {}", s, DATE_FORMATS.join(", ")))
+}
+
+fn de_usd_from_str<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: &str = Deserialize::deserialize(deserializer)?;
+    parse_usd(s).map_err(de::Error::custom)
+}
+
+/// Parses a dollar amount such as "$1,234.56" or "-$1,234.56". Accounting exports may write
+/// negative amounts in parentheses instead, as in "($1,234.56)". Amounts that are not finite,
+/// e.g. "inf", "NaN" or too large for an `f64`, are an error, as they cannot be ordered.
+fn parse_usd(s: &str) -> Result<f64, String> {
+    let (negative, clean_s) = clean_usd(s);
+    let amount = f64::from_str(&clean_s).map_err(|err| err.to_string())?;
+    if !amount.is_finite() {
+        return Err(format!("invalid amount '{}', expected a finite number", s));
+    }
+    Ok(if negative { -amount } else { amount })
+}
+
+/// Parses a dollar amount as `parse_usd` does, exactly into cents. See `Cents::from_str`.
+fn parse_usd_cents(s: &str) -> Result<Cents, String> {
+    let (negative, clean_s) = clean_usd(s);
+    let amount = Cents::from_str(&clean_s)?;
+    Ok(if negative { -amount } else { amount })
+}
+
+/// Returns whether a dollar amount is written in parentheses, for a negative amount, and the
+/// amount without them, the dollar sign and the thousands separators.
+fn clean_usd(s: &str) -> (bool, String) {
+    let trimmed = s.trim();
+    let (negative, unsigned) = match trimmed.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+        Some(unsigned) => (true, unsigned),
+        None => (false, trimmed),
+    };
+    (negative, unsigned.replace(['$', ','], "").trim().to_string())
+}
+
+fn de_cents_from_str<'de, D>(deserializer: D) -> Result<Cents, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: &str = Deserialize::deserialize(deserializer)?;
+    parse_usd_cents(s).map_err(de::Error::custom)
+}
+
+/// Reads a number such as a count of shares, which must be finite.
+fn de_finite<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let n: f64 = Deserialize::deserialize(deserializer)?;
+    if !n.is_finite() {
+        return Err(de::Error::custom(format!("invalid number '{}', expected a finite number", n)));
+    }
+    Ok(n)
+}
+
+fn de_optional_usd_from_str<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: &str = Deserialize::deserialize(deserializer)?;
+    if s.trim().is_empty() {
+        return Ok(None);
+    }
+    parse_usd(s).map(Some).map_err(de::Error::custom)
+}
+
+fn de_fee_from_str<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: &str = Deserialize::deserialize(deserializer)?;
+    if s.trim().is_empty() {
+        return Ok(0.0);
+    }
+    parse_usd(s.trim()).map_err(de::Error::custom)
+}
+
+/// Parses a split ratio written as `<new>:<old>`, e.g. "2:1", or as a number, e.g. "2".
+fn de_ratio_from_str<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: &str = Deserialize::deserialize(deserializer)?;
+    let ratio = match s.find(':') {
+        Some(i) => {
+            let new = f64::from_str(s[..i].trim()).map_err(de::Error::custom)?;
+            let old = f64::from_str(s[i + 1..].trim()).map_err(de::Error::custom)?;
+            new / old
+        }
+        None => f64::from_str(s.trim()).map_err(de::Error::custom)?,
+    };
+    if !(ratio > 0.0 && ratio.is_finite()) {
+        return Err(de::Error::custom(format!("invalid split ratio '{}'", s)));
+    }
+    Ok(ratio)
+}
+
+/// How a transaction changes the shares held.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransactionKind {
+    /// Adds a new lot.
+    Acquisition,
+    /// Adds a new lot of shares bought with a dividend. The lot has its own purchase date and
+    /// price, and so its own holding period, like any other acquisition.
+    Reinvestment,
+    /// Reduces the held lots.
+    Disposal,
+    /// Does not change the shares held.
+    Other,
+}
+
+impl TransactionKind {
+    /// Classifies a transaction type, ignoring case. "Buy" is an acquisition, "Reinvestment",
+    /// "Dividend Reinvest" and "Dividend Reinvestment" are reinvestments, "Sell" is a disposal and
+    /// "Dividend" (paid in cash) is neither. Returns `None` for any other transaction type.
+    /// Reinvestments are only told apart from buys by their transaction type.
+    pub fn from_transaction_type(transaction_type: &str) -> Option<Self> {
+        match transaction_type.trim().to_lowercase().as_str() {
+            "buy" => Some(TransactionKind::Acquisition),
+            "reinvestment" | "dividend reinvest" | "dividend reinvestment" => {
+                Some(TransactionKind::Reinvestment)
+            }
+            "sell" => Some(TransactionKind::Disposal),
+            "dividend" => Some(TransactionKind::Other),
+            _ => None,
+        }
+    }
+
+    /// Whether the transaction adds a new lot.
+    pub fn adds_lot(self) -> bool {
+        match self {
+            TransactionKind::Acquisition | TransactionKind::Reinvestment => true,
+            TransactionKind::Disposal | TransactionKind::Other => false,
+        }
+    }
+}
+
+/// Share counts smaller than this are treated as zero, absorbing rounding error when lots are
+/// reduced by sells.
+const MIN_SHARES: f64 = 1e-6;
+
+/// Proceeds short of the sell target by less than this reach it, absorbing the rounding error of
+/// summing the amounts and taxes of the records in a different order than the sale totals.
+const MIN_AMOUNT: f64 = 1e-6;
+
+/// Shares sold at a loss by a disposal of the account, whose loss is disallowed by the wash-sale
+/// rule if the fund is acquired within `WASH_SALE_DAYS` of the sale.
+#[derive(Clone, Debug)]
+struct WashLoss {
+    fund: String,
+    sale_date: chrono::NaiveDate,
+    num_shares: f64,
+    loss_per_share: f64,
+    /// Days the shares were held, which carry over to the replacement shares.
+    holding_days: i64,
+}
+
+impl WashLoss {
+    fn replaced_by(&self, lot: &Record) -> bool {
+        lot.fund == self.fund && lot.date.signed_duration_since(self.sale_date).num_days().abs() <= WASH_SALE_DAYS
+    }
+}
+
+/// Moves the losses of `losses` that `lot` replaces, up to its shares, onto the basis of the
+/// shares of the lot that replace them. Returns the parts of the lot: one per loss replaced,
+/// dated back by the holding period of the shares sold at the loss, and then the shares that
+/// replace nothing, if any. The parts of the lot that replace a loss can't replace another one.
+fn replace_wash_losses(lot: Record, losses: &mut Vec<WashLoss>) -> Vec<(Record, bool)> {
+    let mut parts = Vec::new();
+    let mut rest = lot;
+    for loss in losses.iter_mut() {
+        if rest.num_shares < MIN_SHARES {
+            break;
+        }
+        if !loss.replaced_by(&rest) {
+            continue;
+        }
+        let num_shares = loss.num_shares.min(rest.num_shares);
+        let share = num_shares / rest.num_shares;
+        let disallowed = loss.loss_per_share * num_shares;
+        let part = Record {
+            date: rest.date - chrono::Duration::days(loss.holding_days),
+            num_shares,
+            share_price: rest.share_price + loss.loss_per_share,
+            amount: rest.amount * share + Cents::from_dollars(rest.amount.signum() * disallowed),
+            fee: rest.fee * share,
+            ..rest.clone()
+        };
+        debug!(
+            "wash sale of {} sold {}: {} shares acquired {} replace the loss of {:.2}",
+            loss.fund, loss.sale_date, num_shares, rest.date, disallowed
+        );
+        parts.push((part, true));
+        rest.amount -= rest.amount * share;
+        rest.fee -= rest.fee * share;
+        rest.num_shares -= num_shares;
+        loss.num_shares -= num_shares;
+    }
+    losses.retain(|loss| loss.num_shares >= MIN_SHARES);
+    if rest.num_shares >= MIN_SHARES {
+        parts.push((rest, false));
+    }
+    parts
+}
+
+pub struct Account {
+    records: Vec<Record>,
+    name: Option<String>,
+    taxable: bool,
+    fund_groups: HashMap<String, String>,
+    adjust_wash_sales: bool,
+}
+
+impl Account {
+    pub fn new(records: Vec<Record>) -> Self {
+        Account {
+            records,
+            name: None,
+            taxable: true,
+            fund_groups: HashMap::new(),
+            adjust_wash_sales: false,
+        }
+    }
+
+    /// Names the account, so that its sell records can be told apart from those of other
+    /// accounts in a `Portfolio`.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Sets whether the capital gains of the account are taxed, e.g. they are not for an IRA.
+    pub fn with_taxable(mut self, taxable: bool) -> Self {
+        self.taxable = taxable;
+        self
+    }
+
+    /// Sets whether `holdings` applies the wash-sale rule to the disposals of the account. A
+    /// disposal at a loss whose fund is acquired within `WASH_SALE_DAYS` before or after it, by
+    /// shares still held after it, has the loss on as many shares disallowed: the loss is added
+    /// to the basis of the replacement shares, which are dated back by the holding period of the
+    /// shares sold. The loss of a disposal is taken from the share price of the disposal less its
+    /// fee and the share price and fee of the lots it reduces. Sales selected by this crate are
+    /// only flagged, see `flag_wash_sales`.
+    pub fn with_wash_sale_adjustment(mut self, adjust: bool) -> Self {
+        self.adjust_wash_sales = adjust;
+        self
+    }
+
+    /// Renames the fund of each record whose fund is a key of `aliases` to the fund it maps to,
+    /// e.g. to the ticker used to look up its price. Other records are unchanged.
+    pub fn with_fund_aliases(mut self, aliases: &HashMap<String, String>) -> Self {
+        for record in self.records.iter_mut() {
+            if let Some(fund) = aliases.get(&record.fund) {
+                record.fund = fund.clone();
+            }
+        }
+        self
+    }
+
+    /// Groups share classes of the same underlying fund, e.g. VTSAX and VTSMX, by mapping each
+    /// class to the name of its group. The records keep their own fund and price, but a fund
+    /// filter naming the group includes every class of it, and the totals of a sale are also
+    /// shown for each group.
+    pub fn with_fund_groups(mut self, fund_groups: &HashMap<String, String>) -> Self {
+        self.fund_groups = fund_groups.clone();
+        self
+    }
+
+    /// Adjusts the records of each split fund dated before the split for the split, so that they
+    /// are in the shares and share prices after it. The amounts are unchanged. A record before
+    /// several splits is adjusted for each of them.
+    pub fn with_splits(mut self, splits: &[Split]) -> Self {
+        for split in splits {
+            for record in self.records.iter_mut() {
+                if record.fund == split.fund && record.date < split.date {
+                    record.num_shares *= split.ratio;
+                    record.share_price /= split.ratio;
+                }
+            }
+        }
+        self
+    }
+
+    /// Checks that the transaction type of every record is known, so that an account can be
+    /// checked before its records are merged with those of other files.
+    fn check_transaction_types(&self) -> Result<(), AccountError> {
+        for record in &self.records {
+            if TransactionKind::from_transaction_type(&record.transaction_type).is_none() {
+                return Err(AccountError::UnknownTransactionType {
+                    transaction_type: record.transaction_type.clone(),
+                    fund: record.fund.clone(),
+                    date: record.date,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the lots currently held in order of purchase. Each acquisition adds a lot and
+    /// each disposal reduces the oldest lots of the same fund first (first-in, first-out). The
+    /// number of shares disposed is taken as the absolute value of the shares transacted, as
+    /// exports may record sells with either sign. Short positions are not supported: an
+    /// acquisition of negative shares, or a disposal of more shares than are held, is an
+    /// `AccountError::ShortPosition` with the shares missing, rather than a lot with negative
+    /// amounts and capital gains, so mis-entered transactions are caught.
+    /// Losses of disposals are moved to replacement shares as set by `with_wash_sale_adjustment`.
+    pub fn holdings(&self) -> Result<Vec<Record>, AccountError> {
+        let mut records: Vec<&Record> = self.records.iter().collect();
+        records.sort_by_key(|r| r.date);
+
+        // each lot with whether it replaces a wash-sale loss already
+        let mut lots: Vec<(Record, bool)> = Vec::new();
+        let mut losses: Vec<WashLoss> = Vec::new();
+        for record in records {
+            let short_position = |shares: f64| AccountError::ShortPosition {
+                fund: record.fund.clone(),
+                date: record.date,
+                shares,
+            };
+            match TransactionKind::from_transaction_type(&record.transaction_type) {
+                Some(TransactionKind::Acquisition) | Some(TransactionKind::Reinvestment) => {
+                    if record.num_shares < 0.0 {
+                        return Err(short_position(-record.num_shares));
+                    }
+                    lots.extend(replace_wash_losses(record.clone(), &mut losses));
+                }
+                Some(TransactionKind::Disposal) => {
+                    let mut remaining = record.num_shares.abs();
+                    for &mut (ref mut lot, _) in lots.iter_mut().filter(|lot| lot.0.fund == record.fund) {
+                        if remaining < MIN_SHARES {
+                            break;
+                        }
+                        let sold = remaining.min(lot.num_shares);
+                        // the fee of the disposal is taken from its proceeds
+                        let sell_price = record.share_price - record.fee / record.num_shares.abs();
+                        let loss_per_share = lot.share_price + lot.fee / lot.num_shares - sell_price;
+                        if self.adjust_wash_sales && loss_per_share > 0.0 {
+                            losses.push(WashLoss {
+                                fund: record.fund.clone(),
+                                sale_date: record.date,
+                                num_shares: sold,
+                                loss_per_share,
+                                holding_days: record.date.signed_duration_since(lot.date).num_days(),
+                            });
+                        }
+                        let kept = (lot.num_shares - sold) / lot.num_shares;
+                        lot.amount = lot.amount * kept;
+                        lot.fee *= kept;
+                        lot.num_shares -= sold;
+                        remaining -= sold;
+                    }
+                    if remaining >= MIN_SHARES {
+                        return Err(short_position(remaining));
+                    }
+                    lots.retain(|lot| lot.0.num_shares >= MIN_SHARES);
+                    // shares acquired before the disposal and still held replace its losses
+                    if !losses.is_empty() {
+                        let held = std::mem::take(&mut lots);
+                        for (lot, replaces) in held {
+                            if replaces {
+                                lots.push((lot, replaces));
+                            } else {
+                                lots.extend(replace_wash_losses(lot, &mut losses));
+                            }
+                        }
+                    }
+                }
+                Some(TransactionKind::Other) => {}
+                None => {
+                    return Err(AccountError::UnknownTransactionType {
+                        transaction_type: record.transaction_type.clone(),
+                        fund: record.fund.clone(),
+                        date: record.date,
+                    });
+                }
+            }
+        }
+
+        Ok(lots.into_iter().map(|(lot, _)| lot).collect())
+    }
+
+    /// Checks that the amount of each record that buys or sells shares is within `tolerance` of
+    /// the share price times the number of shares. Signs are ignored, as exports may record sells
+    /// with negative shares or amounts. Returns an error listing every mismatched record.
+    pub fn validate_amounts(&self, tolerance: f64) -> Result<(), AccountError> {
+        let mut mismatches = Vec::new();
+        for record in &self.records {
+            match TransactionKind::from_transaction_type(&record.transaction_type) {
+                Some(kind) if kind != TransactionKind::Other => {}
+                _ => continue,
+            }
+
+            let expected = (record.share_price * record.num_shares).abs();
+            if (record.amount.abs().to_dollars() - expected).abs() > tolerance {
+                mismatches.push(format!(
+                    "  {}, {}, {}: amount {:.2}, share price * shares {:.2}",
+                    record.date, record.fund, record.transaction_type, record.amount, expected
+                ));
+            }
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(AccountError::MismatchedAmounts(mismatches))
+        }
+    }
+
+    /// Computes the proceeds, capital gains and proceeds after taxes of selling every held lot
+    /// of the funds selected by `options` on `sale_date`.
+    pub fn make_sell_records<'a>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<Vec<SellRecord<'a>>, AccountError> {
+        let mut holdings = self.holdings()?;
+        holdings.retain(|record| {
+            let group = self.fund_groups.get(&record.fund).map(|g| g.as_str());
+            options.funds.includes_class(&record.fund, group)
+        });
+        for record in &holdings {
+            match fund_prices.get(&record.fund) {
+                None => return Err(AccountError::MissingPrice { fund: record.fund.clone() }),
+                Some(&price) if price.is_nan() || price <= 0.0 => {
+                    return Err(AccountError::InvalidPrice { fund: record.fund.clone(), price });
+                }
+                Some(_) => {}
+            }
+        }
+
+        let sell_records = self.sell_records_of(&holdings, fund_prices, tax_rates, sale_date, options);
+        for srec in &sell_records {
+            debug!(
+                "sell record {} {}: {} shares, amount {:.2}, cap gains {:.2}, ratio {:.6}",
+                srec.fund, srec.date_purchased, srec.num_shares, srec.amount, srec.cap_gains, srec.cap_gains_ratio
+            );
+        }
+        Ok(sell_records)
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    fn sell_records_of<'a>(
+        &self,
+        holdings: &[Record],
+        fund_prices: &'a HashMap<String, f64>,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Vec<SellRecord<'a>> {
+        holdings
+            .iter()
+            .map(|record| self.make_sell_record(record, fund_prices, tax_rates, sale_date, options))
+            .collect()
+    }
+
+    /// Computes the sell records of `holdings` on one thread per available core. The chunks are
+    /// joined in order, so the records are in the same order as without the "parallel" feature.
+    #[cfg(feature = "parallel")]
+    fn sell_records_of<'a>(
+        &self,
+        holdings: &[Record],
+        fund_prices: &'a HashMap<String, f64>,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Vec<SellRecord<'a>> {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk_size = holdings.len().div_ceil(threads).max(1);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = holdings
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|record| self.make_sell_record(record, fund_prices, tax_rates, sale_date, options))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+        })
+    }
+
+    /// Computes the sell record of `record`, whose fund must have a price in `fund_prices`. The
+    /// record is sold on the sale date of its fund in `options`, if any, or else on `sale_date`.
+    fn make_sell_record<'a>(
+        &self,
+        record: &Record,
+        fund_prices: &'a HashMap<String, f64>,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> SellRecord<'a> {
+        let sale_date = options.sale_dates.get(&record.fund).cloned().unwrap_or(sale_date);
+        let date_purchased = record.date;
+        let fund = fund_prices.get_key_value(&record.fund).unwrap().0;
+        let num_shares = record.num_shares;
+        // the fee is spread evenly over the shares of the lot, so each share sold carries its
+        // part of the fee in its basis
+        let cost_per_share = if options.basis_from_amount {
+            record.amount.abs().to_dollars() / num_shares
+        } else {
+            record.share_price
+        };
+        let share_price_purchased = cost_per_share + record.fee / num_shares;
+        let share_price = *fund_prices.get(&record.fund).unwrap();
+        // the sale fee is paid once for the lot, however many shares it holds
+        let sale_fee = Cents::from_dollars(options.sale_fee);
+        let amount = Cents::from_dollars(share_price * num_shares) - sale_fee;
+        let cap_gains = Cents::from_dollars((share_price - share_price_purchased) * num_shares) - sale_fee;
+        let cap_gains_ratio = cap_gains.to_dollars() / amount.to_dollars();
+        let holding_days = sale_date.signed_duration_since(date_purchased).num_days();
+        let is_long_term = holding_days > options.long_term_days.unwrap_or(LONG_TERM_DAYS);
+
+        let mut srec = SellRecord {
+            date_purchased,
+            fund,
+            num_shares,
+            share_price_purchased,
+            share_price,
+            amount,
+            cap_gains,
+            cap_gains_ratio,
+            sale_fee,
+            net_amount: amount.to_dollars(),
+            sale_date,
+            holding_days,
+            is_long_term,
+            wash_sale: None,
+            account: self.name.clone(),
+            taxable: self.taxable,
+            fund_group: self.fund_groups.get(&record.fund).cloned(),
+            source_row: record.source_row,
+        };
+        srec.net_amount = amount.to_dollars() - tax_rates.taxes(&srec);
+        srec
+    }
+
+    /// Flags the sell records sold at a loss whose fund was also acquired within `WASH_SALE_DAYS`
+    /// of `sale_date`. This only annotates the records and does not change which are sold.
+    /// Acquisitions on the purchase date of a record are taken to be the record itself. Records
+    /// that are already flagged are left unchanged.
+    pub fn flag_wash_sales(&self, sell_records: &mut [SellRecord], sale_date: chrono::NaiveDate) {
+        for srec in sell_records.iter_mut().filter(|s| s.cap_gains < Cents::ZERO && s.wash_sale.is_none()) {
+            srec.wash_sale = self
+                .records
+                .iter()
+                .filter(|r| r.fund == srec.fund && r.date != srec.date_purchased)
+                .filter(|r| {
+                    let kind = TransactionKind::from_transaction_type(&r.transaction_type);
+                    kind.is_some_and(|kind| kind.adds_lot())
+                })
+                .map(|r| r.date)
+                .find(|&date| sale_date.signed_duration_since(date).num_days().abs() <= WASH_SALE_DAYS);
+        }
+    }
+
+    /// Sells the records with the lowest capital gains ratio first. Records sold at a loss have a
+    /// negative ratio and are sold before any records with gains. Records with the same ratio,
+    /// to within `RATIO_PRECISION`, are sold long-term first, then oldest first, then by fund, so
+    /// the same input always gives the same result.
+    pub fn minimum_cap_gains<'a>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        sell_target: f64,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<SellSummary<'a>, AccountError> {
+        self.select_with(fund_prices, sell_target, tax_rates, sale_date, options, &MinimumCapGains)
+    }
+
+    /// Sells the records with the lowest taxes per dollar of proceeds first, which differs from
+    /// the order of `minimum_cap_gains` when records are taxed at different rates, e.g. short-
+    /// and long-term records or funds with their own rate. Records with the same taxes per dollar
+    /// are sold as in `minimum_cap_gains`.
+    pub fn minimize_tax<'a>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        sell_target: f64,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<SellSummary<'a>, AccountError> {
+        self.select_with(fund_prices, sell_target, tax_rates, sale_date, options, &MinimumTax)
+    }
+
+    /// Sells the records in the order given by `strategy` until the sell target is reached, as
+    /// the other methods of selling do for their own order. The last record may be partially
+    /// sold.
+    pub fn select_with<'a, S: SelectionStrategy + ?Sized>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        sell_target: f64,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+        strategy: &S,
+    ) -> Result<SellSummary<'a>, AccountError> {
+        let mut sell_records = self.make_sell_records(fund_prices, tax_rates, sale_date, options)?;
+        strategy.rank(&mut sell_records, tax_rates);
+        sell_in_order(sell_records, sell_target, tax_rates, options)
+    }
+
+    /// Sells for `sell_target` over two tax years, e.g. to spread the gains of a large sale. The
+    /// records are sold in the order of `minimum_cap_gains`, first on `sale_date` until either
+    /// the target is reached or the capital gains would exceed the limit of `year_limit`, as for
+    /// the room left in a low tax bracket. The rest of the target is sold on the next sale date
+    /// from the records left, whose holding periods then end on that date, with the taxes of
+    /// that year. The last record of each sale may be partially sold.
+    pub fn split_across_years<'a>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        sell_target: f64,
+        year_limit: &YearLimit,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<YearSplit<'a>, AccountError> {
+        let (cap_gains_limit, next_sale_date) = (year_limit.cap_gains, year_limit.next_sale_date);
+        let ranked = self.ranked_sell_records(fund_prices, tax_rates, sale_date, options)?;
+
+        // the records sold this year if the target is not reached first
+        let divisions = options.share_divisions();
+        let mut cap_gains = Cents::ZERO;
+        let mut capped = Vec::new();
+        let mut kept = Vec::new();
+        let mut ranked = ranked.into_iter();
+        for srec in ranked.by_ref() {
+            if !options.can_sell(&srec) {
+                kept.push(srec);
+                continue;
+            }
+            if (cap_gains + srec.cap_gains).to_dollars() <= cap_gains_limit {
+                cap_gains += srec.cap_gains;
+                capped.push(srec);
+                continue;
+            }
+            // only a record with gains can exceed the limit
+            let gains_per_share = srec.share_price - srec.share_price_purchased;
+            let gains = cap_gains_limit - cap_gains.to_dollars() + srec.sale_fee.to_dollars();
+            let n = (gains / gains_per_share * divisions).floor() / divisions;
+            if n > 0.0 {
+                let (sold, rest) = split_record(srec, n, tax_rates);
+                capped.push(sold);
+                kept.push(rest);
+            } else {
+                kept.push(srec);
+            }
+            break;
+        }
+        kept.extend(ranked);
+
+        let available = {
+            let summary = SellSummary::new(capped.clone(), tax_rates);
+            options.target_kind.proceeds(summary.total_amount, summary.total_tax)
+        };
+        if available >= sell_target - MIN_AMOUNT {
+            let first = sell_in_order(capped, sell_target, tax_rates, options)?;
+            let remaining = first.remaining.iter().cloned().chain(kept).collect();
+            return Ok(YearSplit {
+                first: first.with_remaining(remaining),
+                second: None,
+            });
+        }
+
+        let first = SellSummary::new(capped, tax_rates).with_remaining(kept.clone());
+        let mut next: Vec<_> = kept.into_iter().map(|srec| srec.sold_on(next_sale_date, tax_rates, options)).collect();
+        MinimumCapGains.rank(&mut next, tax_rates);
+        let second = sell_in_order(next, sell_target - available, tax_rates, options).map_err(|err| match err {
+            AccountError::InsufficientFunds { available: rest, .. } => AccountError::InsufficientFunds {
+                available: available + rest,
+                target: sell_target,
+            },
+            err => err,
+        })?;
+        Ok(YearSplit {
+            first,
+            second: Some(second),
+        })
+    }
+
+    /// Computes the sell records in the order `minimum_cap_gains` considers them.
+    pub fn ranked_sell_records<'a>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<Vec<SellRecord<'a>>, AccountError> {
+        let mut sell_records = self.make_sell_records(fund_prices, tax_rates, sale_date, options)?;
+        MinimumCapGains.rank(&mut sell_records, tax_rates);
+        Ok(sell_records)
+    }
+
+    /// Returns the lowest share price of `fund`, in whole cents, at which selling its records
+    /// reaches `sell_target`, after taxes unless the target kind of `options` is gross, e.g. for
+    /// the limit price of a sell order. Only the records purchased on `dates` are sold, or every
+    /// record of the fund if `dates` is empty. The proceeds grow with the price, so the price is
+    /// found by bisection.
+    pub fn solve_price(
+        &self,
+        fund: &str,
+        dates: &[chrono::NaiveDate],
+        sell_target: f64,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<f64, AccountError> {
+        if sell_target.is_nan() || sell_target <= 0.0 {
+            return Err(AccountError::InvalidTarget { target: sell_target });
+        }
+        let options = SellOptions {
+            funds: FundFilter::Only(vec![fund.to_string()].into_iter().collect()),
+            ..options.clone()
+        };
+        let holdings = self.holdings()?;
+        if let Some(&date) = dates.iter().find(|&&date| !holdings.iter().any(|r| r.fund == fund && r.date == date)) {
+            return Err(AccountError::RecordNotFound { fund: fund.to_string(), date });
+        }
+        let proceeds = |share_price: f64| -> Result<f64, AccountError> {
+            let mut fund_prices = HashMap::new();
+            fund_prices.insert(fund.to_string(), share_price);
+            let mut sell_records = self.make_sell_records(&fund_prices, tax_rates, sale_date, &options)?;
+            sell_records.retain(|srec| dates.is_empty() || dates.contains(&srec.date_purchased));
+            let summary = SellSummary::new(sell_records, tax_rates);
+            Ok(options.target_kind.proceeds(summary.total_amount, summary.total_tax))
+        };
+
+        // the price is doubled until it reaches the target, and selling fails once it passes
+        // 1e12, e.g. if no shares are held
+        let (mut low, mut high) = (0.0, 1.0);
+        while proceeds(high)? < sell_target {
+            if high > 1e12 {
+                return Err(AccountError::InsufficientFunds {
+                    available: proceeds(high)?,
+                    target: sell_target,
+                });
+            }
+            low = high;
+            high *= 2.0;
+        }
+        while high - low > 1e-6 {
+            let mid = (low + high) / 2.0;
+            // for large prices, low and high are adjacent floats more than 1e-6 apart
+            if mid <= low || mid >= high {
+                break;
+            }
+            if proceeds(mid)? < sell_target {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        // the price reaching the target is between low and high, at most a cent or two above low
+        let mut cents = (low * 100.0).floor();
+        while proceeds(cents / 100.0)? < sell_target - MIN_AMOUNT {
+            cents += 1.0;
+        }
+        Ok(cents / 100.0)
+    }
+
+    /// Sells `target_shares` shares of `fund`, choosing the records with the lowest capital gains
+    /// ratio first as in `minimum_cap_gains`. The last record is partially sold so that exactly
+    /// `target_shares` shares are sold.
+    pub fn minimum_cap_gains_shares<'a>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        fund: &str,
+        target_shares: f64,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<SellSummary<'a>, AccountError> {
+        if target_shares.is_nan() || target_shares <= 0.0 {
+            return Err(AccountError::InvalidTarget { target: target_shares });
+        }
+        let options = SellOptions {
+            funds: FundFilter::Only(vec![fund.to_string()].into_iter().collect()),
+            ..options.clone()
+        };
+        let mut sell_records = self.make_sell_records(fund_prices, tax_rates, sale_date, &options)?;
+        sell_records.sort_by(by_cap_gains_ratio);
+
+        let mut remaining = target_shares;
+        let mut result = Vec::new();
+        let mut kept = Vec::new();
+        let mut sell_records = sell_records.into_iter();
+        for srec in sell_records.by_ref() {
+            if remaining < MIN_SHARES {
+                kept.push(srec);
+                break;
+            }
+            if srec.num_shares <= remaining {
+                remaining -= srec.num_shares;
+                result.push(srec);
+            } else {
+                let (sold, rest) = split_record(srec, remaining, tax_rates);
+                remaining = 0.0;
+                result.push(sold);
+                kept.push(rest);
+            }
+        }
+        kept.extend(sell_records);
+
+        if remaining >= MIN_SHARES {
+            return Err(AccountError::InsufficientShares {
+                fund: fund.to_string(),
+                available: target_shares - remaining,
+                target: target_shares,
+            });
+        }
+
+        Ok(SellSummary::new(result, tax_rates).with_remaining(kept))
+    }
+
+    /// Sells records to realize `target_cap_gains` of capital gains, e.g. to offset a loss that has
+    /// already been realized. The records with the highest capital gains ratio are sold first, so
+    /// that as little as possible is sold. A negative target realizes losses instead, selling the
+    /// records with the lowest ratio first. The last record is partially sold, in whole shares
+    /// unless `options.fractional` is set, so the capital gains can exceed the target slightly.
+    pub fn target_cap_gains<'a>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        target_cap_gains: f64,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<SellSummary<'a>, AccountError> {
+        // work with gains, negating losses when the target is a loss
+        let sign = if target_cap_gains < 0.0 { -1.0 } else { 1.0 };
+        let mut sell_records: Vec<_> = self
+            .make_sell_records(fund_prices, tax_rates, sale_date, options)?
+            .into_iter()
+            .filter(|s| sign*s.cap_gains.to_dollars() > 0.0)
+            .collect();
+        if sign > 0.0 {
+            sell_records.sort_by(|a, b| by_cap_gains_ratio(b, a));
+        } else {
+            sell_records.sort_by(by_cap_gains_ratio);
+        }
+
+        let divisions = options.share_divisions();
+        let mut remaining = sign*target_cap_gains;
+        let mut result = Vec::new();
+        let mut kept = Vec::new();
+        let mut sell_records = sell_records.into_iter();
+        for srec in sell_records.by_ref() {
+            let gains_per_share = sign*(srec.share_price - srec.share_price_purchased);
+            // the sale fee lowers the gains of the shares sold, and so adds to their losses
+            let n = shares_needed(remaining + sign*srec.sale_fee.to_dollars(), gains_per_share, divisions);
+            if n == 0.0 {
+                kept.push(srec);
+                break;
+            } else if n < srec.num_shares {
+                let (sold, rest) = split_record(srec, n, tax_rates);
+                result.push(sold);
+                kept.push(rest);
+                remaining = 0.0;
+                break;
+            }
+            remaining -= sign*srec.cap_gains.to_dollars();
+            result.push(srec);
+        }
+        kept.extend(sell_records);
+
+        if remaining >= MIN_SHARES {
+            return Err(AccountError::InsufficientCapGains {
+                available: target_cap_gains - sign*remaining,
+                target: target_cap_gains,
+            });
+        }
+
+        Ok(SellSummary::new(result, tax_rates).with_remaining(kept))
+    }
+
+    /// Sells the oldest records first (first-in, first-out).
+    pub fn fifo_sell<'a>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        sell_target: f64,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<SellSummary<'a>, AccountError> {
+        self.select_with(fund_prices, sell_target, tax_rates, sale_date, options, &Fifo)
+    }
+
+    /// Sells the most recently purchased records first (last-in, first-out).
+    pub fn lifo_sell<'a>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        sell_target: f64,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<SellSummary<'a>, AccountError> {
+        self.select_with(fund_prices, sell_target, tax_rates, sale_date, options, &Lifo)
+    }
+
+    /// Sells the records with the highest purchase price first (highest-in, first-out). Records
+    /// with the same purchase price are sold oldest first.
+    pub fn hifo_sell<'a>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        sell_target: f64,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<SellSummary<'a>, AccountError> {
+        self.select_with(fund_prices, sell_target, tax_rates, sale_date, options, &Hifo)
+    }
+
+    /// Sells shares using average-cost basis, where every share of a fund has the average cost
+    /// of all held shares of the fund. Funds with the lowest capital gains ratio are sold first.
+    /// The shares of a fund are sold oldest first, as this determines their holding period.
+    pub fn average_cost_sell<'a>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        sell_target: f64,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<SellSummary<'a>, AccountError> {
+        self.select_with(fund_prices, sell_target, tax_rates, sale_date, options, &AverageCost)
+    }
+}
+
+/// An order in which the records of an account are sold for a sell target, given to
+/// `Account::select_with`. The built-in methods of selling are implemented as strategies, and a
+/// closure taking the records and tax rates is a strategy too.
+pub trait SelectionStrategy {
+    /// Orders `sell_records` in the order they are sold. A strategy may also change the records,
+    /// e.g. their cost basis, as long as their taxes and net amount are updated with `tax_rates`.
+    fn rank(&self, sell_records: &mut [SellRecord], tax_rates: &TaxRates);
+}
+
+impl<F: Fn(&mut [SellRecord], &TaxRates)> SelectionStrategy for F {
+    fn rank(&self, sell_records: &mut [SellRecord], tax_rates: &TaxRates) {
+        self(sell_records, tax_rates)
+    }
+}
+
+/// Sells the records with the lowest capital gains ratio first. See `Account::minimum_cap_gains`.
+#[derive(Clone, Copy, Debug)]
+pub struct MinimumCapGains;
+
+impl SelectionStrategy for MinimumCapGains {
+    fn rank(&self, sell_records: &mut [SellRecord], _: &TaxRates) {
+        sell_records.sort_by(by_cap_gains_ratio);
+    }
+}
+
+/// Sells the records with the lowest taxes per dollar first. See `Account::minimize_tax`.
+#[derive(Clone, Copy, Debug)]
+pub struct MinimumTax;
+
+impl SelectionStrategy for MinimumTax {
+    fn rank(&self, sell_records: &mut [SellRecord], tax_rates: &TaxRates) {
+        let tax_ratio = |srec: &SellRecord| {
+            (tax_rates.taxes(srec) / srec.amount.to_dollars() / RATIO_PRECISION).round()
+        };
+        sell_records.sort_by(|a, b| {
+            tax_ratio(a).total_cmp(&tax_ratio(b)).then_with(|| by_cap_gains_ratio(a, b))
+        });
+    }
+}
+
+/// Sells the oldest records first. See `Account::fifo_sell`.
+#[derive(Clone, Copy, Debug)]
+pub struct Fifo;
+
+impl SelectionStrategy for Fifo {
+    fn rank(&self, sell_records: &mut [SellRecord], _: &TaxRates) {
+        sell_records.sort_by_key(|s| s.date_purchased);
+    }
+}
+
+/// Sells the most recently purchased records first. See `Account::lifo_sell`.
+#[derive(Clone, Copy, Debug)]
+pub struct Lifo;
+
+impl SelectionStrategy for Lifo {
+    fn rank(&self, sell_records: &mut [SellRecord], _: &TaxRates) {
+        sell_records.sort_by_key(|s| Reverse(s.date_purchased));
+    }
+}
+
+/// Sells the records with the highest purchase price first. See `Account::hifo_sell`.
+#[derive(Clone, Copy, Debug)]
+pub struct Hifo;
+
+impl SelectionStrategy for Hifo {
+    fn rank(&self, sell_records: &mut [SellRecord], _: &TaxRates) {
+        sell_records.sort_by(|a, b| {
+            b.share_price_purchased
+                .total_cmp(&a.share_price_purchased)
+                .then(a.date_purchased.cmp(&b.date_purchased))
+        });
+    }
+}
+
+/// Sells shares at the average cost of their fund. See `Account::average_cost_sell`.
+#[derive(Clone, Copy, Debug)]
+pub struct AverageCost;
+
+impl SelectionStrategy for AverageCost {
+    fn rank(&self, sell_records: &mut [SellRecord], tax_rates: &TaxRates) {
+        // total shares and cost of each fund
+        let mut positions: HashMap<&str, (f64, f64)> = HashMap::new();
+        for srec in sell_records.iter() {
+            let position = positions.entry(srec.fund).or_insert((0.0, 0.0));
+            position.0 += srec.num_shares;
+            position.1 += srec.num_shares * srec.share_price_purchased;
+        }
+        for srec in sell_records.iter_mut() {
+            let (num_shares, cost) = positions[srec.fund];
+            srec.share_price_purchased = cost / num_shares;
+            let cap_gains = Cents::from_dollars((srec.share_price - srec.share_price_purchased) * srec.num_shares);
+            srec.cap_gains = cap_gains - srec.sale_fee;
+            srec.cap_gains_ratio = srec.cap_gains.to_dollars() / srec.amount.to_dollars();
+            srec.net_amount = srec.amount.to_dollars() - tax_rates.taxes(srec);
+        }
+
+        sell_records.sort_by(|a, b| {
+            a.cap_gains_ratio
+                .total_cmp(&b.cap_gains_ratio)
+                .then(a.fund.cmp(b.fund))
+                .then(a.date_purchased.cmp(&b.date_purchased))
+        });
+    }
+}
+
+/// Capital gains ratios are compared in multiples of this, so that ratios differing only by
+/// rounding error are ties.
+const RATIO_PRECISION: f64 = 1e-6;
+
+/// Orders sell records by capital gains ratio, breaking ties by preferring long-term records,
+/// whose gains are taxed less, then by purchase date, fund and purchase price.
+fn by_cap_gains_ratio(a: &SellRecord, b: &SellRecord) -> Ordering {
+    let ratio = |srec: &SellRecord| (srec.cap_gains_ratio / RATIO_PRECISION).round();
+    ratio(a)
+        .total_cmp(&ratio(b))
+        .then(b.is_long_term.cmp(&a.is_long_term))
+        .then(a.date_purchased.cmp(&b.date_purchased))
+        .then(a.fund.cmp(b.fund))
+        .then(a.share_price_purchased.total_cmp(&b.share_price_purchased))
+}
+
+/// Accounts sold from together, such as a taxable brokerage account and an IRA.
+pub struct Portfolio {
+    accounts: Vec<Account>,
+}
+
+impl Portfolio {
+    pub fn new(accounts: Vec<Account>) -> Self {
+        Portfolio { accounts }
+    }
+
+    pub fn accounts(&self) -> &[Account] {
+        &self.accounts
+    }
+
+    /// Computes the sell records of every held lot of every account. See
+    /// `Account::make_sell_records`.
+    pub fn make_sell_records<'a>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<Vec<SellRecord<'a>>, AccountError> {
+        let mut vec = Vec::new();
+        for account in &self.accounts {
+            vec.extend(account.make_sell_records(fund_prices, tax_rates, sale_date, options)?);
+        }
+        Ok(vec)
+    }
+
+    /// Sells the records of tax-advantaged accounts first, then the records of taxable accounts,
+    /// each with the lowest capital gains ratio first as in `Account::minimum_cap_gains`.
+    pub fn minimum_cap_gains<'a>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        sell_target: f64,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<SellSummary<'a>, AccountError> {
+        let sell_records = self.ranked_sell_records(fund_prices, tax_rates, sale_date, options)?;
+        sell_in_order(sell_records, sell_target, tax_rates, options)
+    }
+
+    /// Computes the sell records of every account in the order `minimum_cap_gains` considers
+    /// them.
+    pub fn ranked_sell_records<'a>(
+        &self,
+        fund_prices: &'a HashMap<String, f64>,
+        tax_rates: &TaxRates,
+        sale_date: chrono::NaiveDate,
+        options: &SellOptions,
+    ) -> Result<Vec<SellRecord<'a>>, AccountError> {
+        let mut sell_records = self.make_sell_records(fund_prices, tax_rates, sale_date, options)?;
+        sell_records.sort_by(|a, b| {
+            a.taxable.cmp(&b.taxable).then_with(|| by_cap_gains_ratio(a, b))
+        });
+        Ok(sell_records)
+    }
+
+    /// Flags wash sales as in `Account::flag_wash_sales`, taking acquisitions in any account into
+    /// account.
+    pub fn flag_wash_sales(&self, sell_records: &mut [SellRecord], sale_date: chrono::NaiveDate) {
+        for account in &self.accounts {
+            account.flag_wash_sales(sell_records, sale_date);
+        }
+    }
+}
+
+/// Smallest fraction of a share sold when selling fractional shares.
+pub const FRACTIONAL_SHARE: f64 = 0.001;
+
+/// Funds whose records may be sold.
+#[derive(Clone, Debug, Default)]
+pub enum FundFilter {
+    /// Every fund may be sold.
+    #[default]
+    All,
+    /// Only the given funds may be sold.
+    Only(HashSet<String>),
+    /// The given funds may not be sold.
+    Exclude(HashSet<String>),
+}
+
+impl FundFilter {
+    pub fn includes(&self, fund: &str) -> bool {
+        match *self {
+            FundFilter::All => true,
+            FundFilter::Only(ref funds) => funds.contains(fund),
+            FundFilter::Exclude(ref funds) => !funds.contains(fund),
+        }
+    }
+
+    /// Returns whether a share class `fund` of the fund group `group`, if any, is included. A
+    /// class is included if either it or its group is given with `Only`, and excluded if either
+    /// is given with `Exclude`.
+    pub fn includes_class(&self, fund: &str, group: Option<&str>) -> bool {
+        match *self {
+            FundFilter::All => true,
+            FundFilter::Only(_) => self.includes(fund) || group.is_some_and(|g| self.includes(g)),
+            FundFilter::Exclude(_) => self.includes(fund) && group.is_none_or(|g| self.includes(g)),
+        }
+    }
+}
+
+/// Whether a sell target is for the proceeds after or before taxes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TargetKind {
+    /// The proceeds after the taxes owed on the capital gains of the sale.
+    #[default]
+    Net,
+    Gross,
+}
+
+impl TargetKind {
+    /// Returns the proceeds of a sale counted towards a target of this kind.
+    pub fn proceeds(self, amount: f64, taxes: f64) -> f64 {
+        match self {
+            TargetKind::Net => amount - taxes,
+            TargetKind::Gross => amount,
+        }
+    }
+}
+
+impl FromStr for TargetKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "net" => Ok(TargetKind::Net),
+            "gross" => Ok(TargetKind::Gross),
+            _ => Err("expected one of net, gross".to_string()),
+        }
+    }
+}
+
+/// Options controlling how records are sold.
+#[derive(Clone, Debug, Default)]
+pub struct SellOptions {
+    /// Sell the last record in multiples of `FRACTIONAL_SHARE` instead of whole shares, so that
+    /// the sell target is exceeded by less than the value of a thousandth of a share.
+    pub fractional: bool,
+    /// Never sell part of a record. The last record is either sold whole or not at all, whichever
+    /// gets closer to the sell target, so the target may not be reached. Ignored when selling a
+    /// target number of shares or capital gains.
+    pub whole_lots: bool,
+    /// Funds considered for sale. Records of other funds are kept and don't need a price.
+    pub funds: FundFilter,
+    /// Smallest number of shares sold of a record, to avoid trivial sales. Records holding fewer
+    /// shares are not sold and a partially sold record sells at least this many shares, so more
+    /// than needed may be sold. If the records that can be sold are worth less than the sell
+    /// target, selling fails with `AccountError::InsufficientFunds`. Ignored when selling a
+    /// target number of shares or capital gains.
+    pub min_shares: f64,
+    /// Whether the sell target is for the proceeds after or before taxes.
+    pub target_kind: TargetKind,
+    /// Largest number of records sold. Records are still chosen in order, but a record is passed
+    /// over if the target could then no longer be reached with the records left, so the capital
+    /// gains can be higher than without a limit. If no `max_lots` records reach the target,
+    /// selling fails with `AccountError::InsufficientFundsInLots`. Ignored when selling a target
+    /// number of shares or capital gains.
+    pub max_lots: Option<usize>,
+    /// Latest purchase date of the records sold, e.g. to avoid short-term gains. Records purchased
+    /// later are not sold, like records with fewer than `min_shares` shares. Ignored when selling
+    /// a target number of shares or capital gains.
+    pub no_sell_after: Option<chrono::NaiveDate>,
+    /// Sale dates of funds sold on another day than the sale date, e.g. from the Date column of
+    /// the fund prices file. The holding period of their records ends on the fund's date.
+    pub sale_dates: SaleDates,
+    /// Records held for more than this many days are long term, for the holding period of
+    /// another jurisdiction than the US. `None` is `LONG_TERM_DAYS`.
+    pub long_term_days: Option<i64>,
+    /// Take the cost basis of a record from its Amount divided by its shares instead of from its
+    /// Share price, for brokers whose amounts include rounding or costs the share price leaves
+    /// out. The Amount wins over the Share price either way it differs. A Fee is still added, so
+    /// it must not also be part of the Amount.
+    pub basis_from_amount: bool,
+    /// Fee paid for selling each record, e.g. a commission per trade. The fee is per lot, not per
+    /// share or per sale: each record sold pays it once, however many shares it sells. It's taken
+    /// from the amount of the record, and so lowers its capital gains. A partially sold record
+    /// pays the whole fee, so enough more shares are sold to cover it. A record worth no more than
+    /// the fee is not sold when selling for an amount.
+    pub sale_fee: f64,
+}
+
+/// The sale date of each fund sold on its own date.
+pub type SaleDates = HashMap<String, chrono::NaiveDate>;
+
+impl SellOptions {
+    /// Returns the number of parts a share can be sold in.
+    fn share_divisions(&self) -> f64 {
+        if self.fractional {
+            1.0 / FRACTIONAL_SHARE
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns whether `srec` can be sold when selling for an amount. A record worth no more
+    /// than the sale fee brings in nothing and is not sold.
+    pub fn can_sell(&self, srec: &SellRecord) -> bool {
+        srec.amount > Cents::ZERO
+            && srec.num_shares >= self.min_shares
+            && self.no_sell_after.is_none_or(|date| srec.date_purchased <= date)
+    }
+}
+
+/// An `f64` ordered by `f64::total_cmp`, to be a key of ordered collections.
+#[derive(Clone, Copy, Debug)]
+struct TotalOrder(f64);
+
+impl PartialEq for TotalOrder {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TotalOrder {}
+
+impl PartialOrd for TotalOrder {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TotalOrder {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Splits `sell_records` into at most `max_lots` records to sell, in order, and the records
+/// passed over. A record is passed over if the records left can't make up the rest of the
+/// target after selling it.
+fn limit_lots<'a>(
+    sell_records: Vec<SellRecord<'a>>,
+    sell_target: f64,
+    max_lots: usize,
+    options: &SellOptions,
+) -> Result<(Vec<SellRecord<'a>>, Vec<SellRecord<'a>>), AccountError> {
+    let value = |srec: &SellRecord| {
+        let amount = srec.amount.to_dollars();
+        options.target_kind.proceeds(amount, amount - srec.net_amount).max(0.0)
+    };
+    // the values of the records not yet considered
+    let mut rest: BTreeSet<(TotalOrder, usize)> = sell_records
+        .iter()
+        .enumerate()
+        .filter(|&(_, srec)| options.can_sell(srec))
+        .map(|(i, srec)| (TotalOrder(value(srec)), i))
+        .collect();
+    let largest = |rest: &BTreeSet<(TotalOrder, usize)>, n: usize| -> f64 {
+        rest.iter().rev().take(n).map(|&(TotalOrder(v), _)| v).sum()
+    };
+
+    let available = largest(&rest, max_lots);
+    if available < sell_target {
+        return Err(AccountError::InsufficientFundsInLots {
+            max_lots,
+            available,
+            target: sell_target,
+        });
+    }
+
+    let mut lots = max_lots;
+    let mut shortfall = sell_target;
+    let mut selected = vec![false; sell_records.len()];
+    for (i, srec) in sell_records.iter().enumerate() {
+        if shortfall <= 0.0 || lots == 0 {
+            break;
+        }
+        if !options.can_sell(srec) {
+            continue;
+        }
+        let v = value(srec);
+        rest.remove(&(TotalOrder(v), i));
+        if v >= shortfall || v + largest(&rest, lots - 1) >= shortfall {
+            selected[i] = true;
+            shortfall -= v;
+            lots -= 1;
+        }
+    }
+
+    let (sold, passed): (Vec<_>, Vec<_>) = sell_records.into_iter().zip(selected).partition(|&(_, s)| s);
+    let records = |pairs: Vec<(SellRecord<'a>, bool)>| pairs.into_iter().map(|(srec, _)| srec).collect();
+    Ok((records(sold), records(passed)))
+}
+
+/// Sells records in the given order until the proceeds, after taxes unless the target kind of
+/// `options` is gross, reach the sell target. The last record may be partially sold.
+fn sell_in_order<'a>(
+    sell_records: Vec<SellRecord<'a>>,
+    sell_target: f64,
+    tax_rates: &TaxRates,
+    options: &SellOptions,
+) -> Result<SellSummary<'a>, AccountError> {
+    if sell_target.is_nan() || sell_target <= 0.0 {
+        return Err(AccountError::InvalidTarget { target: sell_target });
+    }
+
+    let (sell_records, mut kept) = match options.max_lots {
+        Some(max_lots) => limit_lots(sell_records, sell_target, max_lots, options)?,
+        None => (sell_records, Vec::new()),
+    };
+
+    let divisions = options.share_divisions();
+    let min_shares = (options.min_shares*divisions).ceil()/divisions;
+    let proceeds = |amount: f64, taxes: &SaleTaxes| {
+        options.target_kind.proceeds(amount, taxes_owed(taxes.total(tax_rates)))
+    };
+    let mut amount = 0.0;
+    let mut taxes = SaleTaxes::default();
+    let mut reached = false;
+    let mut result = Vec::new();
+    let mut sell_records = sell_records.into_iter();
+    for srec in sell_records.by_ref() {
+        if !options.can_sell(&srec) {
+            kept.push(srec);
+            continue;
+        }
+
+        // get pre-record values for amount and taxes
+        let a = amount;
+        let before = taxes.clone();
+        amount += srec.amount.to_dollars();
+        taxes.add(tax_rates, &srec);
+        debug!(
+            "selling {} {}: proceeds {:.2} of target {:.2}",
+            srec.fund,
+            srec.date_purchased,
+            proceeds(amount, &taxes),
+            sell_target
+        );
+
+        if proceeds(amount, &taxes) >= sell_target - MIN_AMOUNT {
+            reached = true;
+
+            if options.whole_lots {
+                // stop short of the target if that is closer, preferring to reach it on a tie
+                let overshoot = proceeds(amount, &taxes) - sell_target;
+                let undershoot = sell_target - proceeds(a, &before);
+                if undershoot < overshoot {
+                    kept.push(srec);
+                } else {
+                    result.push(srec);
+                }
+                break;
+            }
+
+            // see if we can sell some (not all) of the shares of this record
+            let n = match options.target_kind {
+                TargetKind::Gross => {
+                    shares_needed(sell_target - a + srec.sale_fee.to_dollars(), srec.share_price, divisions)
+                }
+                // the taxes don't grow linearly with the shares sold, nor does the net amount
+                // less a sale fee
+                TargetKind::Net
+                    if tax_rates.uses_brackets(&srec)
+                        || tax_rates.carryover_loss > 0.0
+                        || srec.sale_fee != Cents::ZERO =>
+                {
+                    searched_shares_needed(&srec, a, &before, sell_target, tax_rates, divisions)
+                }
+                TargetKind::Net => {
+                    let x = (srec.amount.to_dollars() - tax_rates.taxes(&srec)) / srec.num_shares;
+                    let t = before.total(tax_rates);
+
+                    // get number of shares needed to reach sell target
+                    // as taxes owed are never negative, the net amount is the lesser of the
+                    // amount and the amount after taxes and both must reach the sell target
+                    shares_needed(sell_target - a, srec.share_price, divisions)
+                        .max(shares_needed(sell_target - (a - t), x, divisions))
+                }
+            };
+
+            if n == 0.0 {
+                kept.push(srec);
+            } else if n.max(min_shares) < srec.num_shares {
+                let (sold, rest) = split_record(srec, n.max(min_shares), tax_rates);
+                result.push(sold);
+                kept.push(rest);
+            } else {
+                result.push(srec);
+            }
+            break;
+        } else {
+            result.push(srec);
+        }
+    }
+    kept.extend(sell_records);
+
+    if !reached {
+        return Err(AccountError::InsufficientFunds {
+            available: proceeds(amount, &taxes),
+            target: sell_target,
+        });
+    }
+
+    Ok(SellSummary::new(result, tax_rates).with_remaining(kept))
+}
+
+/// Returns the fewest shares of a record that must be sold, after records with amount `a` and
+/// taxes `before`, to reach `sell_target`, for taxes that don't grow linearly with the shares
+/// sold. The record as a whole must reach it. Shares can only be sold in whole multiples of
+/// `1/divisions`.
+fn searched_shares_needed(
+    srec: &SellRecord,
+    a: f64,
+    before: &SaleTaxes,
+    sell_target: f64,
+    tax_rates: &TaxRates,
+    divisions: f64,
+) -> f64 {
+    let reaches = |k: f64| {
+        let part = srec.part((k / divisions).min(srec.num_shares));
+        let mut taxes = before.clone();
+        taxes.add(tax_rates, &part);
+        a + part.amount.to_dollars() - taxes_owed(taxes.total(tax_rates)) >= sell_target - MIN_AMOUNT
+    };
+
+    // the taxes of each share depend on the gains sold before it, but the net amount still
+    // grows with every share sold, so search for the fewest shares
+    let (mut lo, mut hi) = (0.0, (srec.num_shares * divisions).ceil());
+    if reaches(lo) {
+        return 0.0;
+    }
+    while hi - lo > 1.0 {
+        let mid = ((lo + hi) / 2.0_f64).floor();
+        if reaches(mid) {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    (hi / divisions).min(srec.num_shares)
+}
+
+/// Returns the number of shares, each adding `value` to the net amount, that must be sold to
+/// reach `shortfall`. Shares can only be sold in whole multiples of `1/divisions`.
+fn shares_needed(shortfall: f64, value: f64, divisions: f64) -> f64 {
+    // allow for rounding error so that an exact multiple doesn't need an extra part
+    (shortfall / value * divisions - MIN_SHARES).ceil().max(0.0) / divisions
+}
+
+/// Filename used to read input from stdin.
+pub const STDIN: &str = "-";
+
+/// Conventions for writing amounts of a currency, used to read the amounts and prices of the
+/// input files and to print totals.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Currency {
+    /// ISO 4217 code, e.g. "USD".
+    pub code: String,
+    pub symbol: String,
+    pub thousands_separator: char,
+    pub decimal_separator: char,
+}
+
+impl Currency {
+    fn new(code: &str, symbol: &str, thousands_separator: char, decimal_separator: char) -> Self {
+        Currency {
+            code: code.to_string(),
+            symbol: symbol.to_string(),
+            thousands_separator,
+            decimal_separator,
+        }
+    }
+
+    /// Rewrites an amount such as "1.234,50 €" as a plain number, "1234.50".
+    fn normalize(&self, s: &str) -> String {
+        s.replace(self.symbol.as_str(), "")
+            .trim()
+            .replace(self.thousands_separator, "")
+            .replace(self.decimal_separator, ".")
+    }
+
+    /// Formats `amount` with the currency symbol and `precision` decimal places.
+    pub fn format(&self, amount: f64, precision: usize) -> String {
+        if amount < 0.0 {
+            format!("-{}{:.*}", self.symbol, precision, -amount)
+        } else {
+            format!("{}{:.*}", self.symbol, precision, amount)
+        }
+    }
+
+    /// Formats `amount` as `format` does, with the thousands and decimal separators of the
+    /// currency, e.g. "$123,456.78" or "€123.456,78".
+    pub fn format_grouped(&self, amount: f64, precision: usize) -> String {
+        let digits = format!("{:.*}", precision, amount.abs());
+        let (whole, fraction) = match digits.find('.') {
+            Some(i) => (&digits[..i], &digits[i + 1..]),
+            None => (digits.as_str(), ""),
+        };
+        let mut grouped = String::new();
+        for (i, c) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                grouped.push(self.thousands_separator);
+            }
+            grouped.push(c);
+        }
+        if !fraction.is_empty() {
+            grouped.push(self.decimal_separator);
+            grouped.push_str(fraction);
+        }
+        let sign = if amount < 0.0 { "-" } else { "" };
+        format!("{}{}{}", sign, self.symbol, grouped)
+    }
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Currency::new("USD", "$", ',', '.')
+    }
+}
+
+impl FromStr for Currency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "USD" => Ok(Currency::default()),
+            "EUR" => Ok(Currency::new("EUR", "€", '.', ',')),
+            "GBP" => Ok(Currency::new("GBP", "£", ',', '.')),
+            _ => Err("expected one of USD, EUR, GBP".to_string()),
+        }
+    }
+}
+
+/// Format of the input files.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputFormat {
+    Csv,
+    /// An array of objects whose members are the fields of the rows of a CSV file.
+    Json,
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(InputFormat::Csv),
+            "json" => Ok(InputFormat::Json),
+            _ => Err("expected one of csv, json".to_string()),
+        }
+    }
+}
+
+/// Options controlling how input files are read.
+#[derive(Clone, Debug, Default)]
+pub struct InputOptions {
+    /// Maps column names used in the input files to the default column names, e.g. "Trade Date"
+    /// to "Date". Columns not in the map keep their names.
+    pub columns: HashMap<String, String>,
+    /// Format of the dates in account files, used instead of `DATE_FORMATS`. See
+    /// `chrono::format::strftime` for the syntax.
+    pub date_format: Option<String>,
+    /// Currency the amounts and prices are written in.
+    pub currency: Currency,
+    /// Whether the input files are gzip-compressed. Files ending in ".gz" are always
+    /// decompressed.
+    pub gzip: bool,
+    /// Format of the input files. If not set, files ending in ".json" or ".json.gz" are read as
+    /// JSON and other files as CSV.
+    pub format: Option<InputFormat>,
+    /// Delimiter of the fields of csv input files, e.g. b'\t' for tab-separated files. If not
+    /// set, fields are separated by commas.
+    pub delimiter: Option<u8>,
+    /// Which price is read of a fund with several prices a day in the fund prices file.
+    pub price_time: PriceTime,
+}
+
+impl InputOptions {
+    /// Reads the column `name` in the input files as the default column `default_name`.
+    pub fn rename_column(&mut self, name: &str, default_name: &str) {
+        self.columns.insert(name.to_string(), default_name.to_string());
+    }
+
+    fn format_of(&self, filename: &str) -> InputFormat {
+        match self.format {
+            Some(format) => format,
+            None if filename.ends_with(".json") || filename.ends_with(".json.gz") => InputFormat::Json,
+            None => InputFormat::Csv,
+        }
+    }
+
+    fn column_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.columns.get(name).map_or(name, |s| s.as_str())
+    }
+
+    /// Rewrites the date in field `column` of `row` from `date_format`, if set, to ISO 8601, which
+    /// is one of `DATE_FORMATS`.
+    fn normalize_date(
+        &self,
+        row: csv::StringRecord,
+        column: Option<usize>,
+    ) -> Result<csv::StringRecord, Error> {
+        let (format, column) = match (&self.date_format, column) {
+            (Some(format), Some(column)) => (format, column),
+            _ => return Ok(row),
+        };
+
+        let mut normalized = csv::StringRecord::new();
+        for (i, field) in row.iter().enumerate() {
+            if i != column {
+                normalized.push_field(field);
+                continue;
+            }
+            let date = chrono::NaiveDate::parse_from_str(field.trim(), format).map_err(|_| {
+                let line = row.position().map_or(0, |p| p.line());
+                AccountError::InvalidDate {
+                    date: field.to_string(),
+                    line,
+                    format: format.clone(),
+                }
+            })?;
+            normalized.push_field(&date.format("%Y-%m-%d").to_string());
+        }
+        normalized.set_position(row.position().cloned());
+        Ok(normalized)
+    }
+
+    /// Rewrites the amounts in the fields `columns` of `row` from the conventions of `currency` to
+    /// plain numbers. Rows are read as they are in the default currency.
+    fn normalize_amounts(&self, row: csv::StringRecord, columns: &[usize]) -> csv::StringRecord {
+        if self.currency == Currency::default() {
+            return row;
+        }
+
+        let mut normalized = csv::StringRecord::new();
+        for (i, field) in row.iter().enumerate() {
+            if columns.contains(&i) {
+                normalized.push_field(&self.currency.normalize(field));
+            } else {
+                normalized.push_field(field);
+            }
+        }
+        normalized.set_position(row.position().cloned());
+        normalized
+    }
+}
+
+/// Returns the indices of the `columns` in `headers`.
+fn column_indices(headers: &csv::StringRecord, columns: &[&str]) -> Vec<usize> {
+    headers.iter().enumerate().filter(|&(_, h)| columns.contains(&h)).map(|(i, _)| i).collect()
+}
+
+/// Opens the gzip file `filename`, or stdin if `filename` is `STDIN`, for reading its
+/// decompressed contents.
+fn gunzip(filename: &str) -> io::Result<gzip::Decoder<Box<dyn io::Read>>> {
+    let input: Box<dyn io::Read> = if filename == STDIN {
+        Box::new(io::stdin())
+    } else {
+        Box::new(File::open(filename)?)
+    };
+    Ok(gzip::Decoder::new(input))
+}
+
+/// Rewrites the JSON array of objects read from `input` as CSV, so that it's read like a CSV
+/// file. See `json::to_rows`.
+fn json_to_csv(mut input: Box<dyn io::Read>) -> Result<Vec<u8>, Error> {
+    let mut s = String::new();
+    input.read_to_string(&mut s).map_err(csv::Error::from)?;
+    let (headers, rows) = json::to_rows(&json::parse(&s)?)?;
+
+    let mut wtr = csv::Writer::from_writer(Vec::new());
+    wtr.write_record(&headers)?;
+    for row in rows {
+        wtr.write_record(&row)?;
+    }
+    Ok(wtr.into_inner().expect("writing to a Vec can't fail"))
+}
+
+/// Opens a CSV reader for `filename`, or for stdin if `filename` is `STDIN`. Input without a
+/// header row is an error.
+fn open_csv(filename: &str, options: &InputOptions) -> Result<csv::Reader<Box<dyn io::Read>>, Error> {
+    let open = || -> Result<csv::Reader<Box<dyn io::Read>>, Error> {
+        let mut input: Box<dyn io::Read> = if options.gzip || filename.ends_with(".gz") {
+            Box::new(gunzip(filename).map_err(csv::Error::from)?)
+        } else if filename == STDIN {
+            Box::new(io::stdin())
+        } else {
+            Box::new(File::open(filename).map_err(csv::Error::from)?)
+        };
+        let mut builder = csv::ReaderBuilder::new();
+        // json is converted to csv with the default delimiter
+        if options.format_of(filename) == InputFormat::Json {
+            input = Box::new(io::Cursor::new(json_to_csv(input)?));
+        } else if let Some(delimiter) = options.delimiter {
+            builder.delimiter(delimiter);
+        }
+
+        let mut rdr = builder.from_reader(input);
+        if rdr.headers()?.is_empty() {
+            return Err(Error::EmptyInput);
+        }
+        if !options.columns.is_empty() {
+            let headers = rdr.headers()?.iter().map(|h| options.column_name(h)).collect();
+            rdr.set_headers(headers);
+        }
+        Ok(rdr)
+    };
+
+    open().map_err(|err| err.in_file(filename))
+}
+
+pub fn load_account(filename: &str, options: &InputOptions) -> Result<Account, Error> {
+    let mut rdr = open_csv(filename, options)?;
+    let headers = rdr.headers().map_err(|err| Error::from(err).in_file(filename))?.clone();
+    let date_column = headers.iter().position(|h| h == "Date");
+    let amount_columns = column_indices(&headers, &["Shares transacted", "Share price", "Amount", "Fee"]);
+    let mut vec = Vec::new();
+
+    for (i, result) in rdr.records().enumerate() {
+        let record = result.map_err(Error::from).and_then(|row| {
+            let row = options.normalize_amounts(options.normalize_date(row, date_column)?, &amount_columns);
+            row.deserialize::<Record>(Some(&headers)).map_err(Error::from)
+        });
+        match record {
+            Ok(record) => {
+                let record = Record {
+                    source_row: Some(i as u64 + 1),
+                    ..record
+                };
+                trace!("{}: {:?}", filename, record);
+                vec.push(record)
+            }
+            Err(err) => return Err(Error::Record(i as u64 + 1, Box::new(err)).in_file(filename)),
+        }
+    }
+
+    // an account without records can only fail later with a misleading "Insufficient funds."
+    if vec.is_empty() {
+        let filename = if filename == STDIN { "<stdin>" } else { filename };
+        return Err(Error::Account(AccountError::NoRecords { filename: filename.to_string() }));
+    }
+
+    debug!("read {} records from {}", vec.len(), filename);
+    Ok(Account::new(vec))
+}
+
+/// Reads the records of several files, e.g. one per year, as a single account. A file given
+/// more than once, by the same or another path, is only read once. Errors name the file of the
+/// record, including unknown transaction types, which are checked before the records are
+/// merged.
+pub fn load_accounts(filenames: &[&str], options: &InputOptions) -> Result<Account, Error> {
+    let mut paths = HashSet::new();
+    let mut records = Vec::new();
+    for &filename in filenames {
+        // a file that can't be found is left to fail when it's loaded
+        let path = fs::canonicalize(filename).unwrap_or_else(|_| filename.into());
+        if filename != STDIN && !paths.insert(path) {
+            continue;
+        }
+        let account = load_account(filename, options)?;
+        account.check_transaction_types().map_err(|err| Error::from(err).in_file(filename))?;
+        records.extend(account.records);
+    }
+    Ok(Account::new(records))
+}
+
+/// Reads the share price of each fund. A fund may be listed more than once with prices at
+/// different times of the day, of which the price selected by `InputOptions::price_time` is
+/// read, or else only with the same price each time. A fund without a price at or before the
+/// time `PriceTime::At` selects is left out.
+pub fn load_fund_prices(filename: &str, options: &InputOptions) -> Result<HashMap<String, f64>, Error> {
+    load_fund_prices_and_dates(filename, options).map(|(fund_prices, _)| fund_prices)
+}
+
+/// Reads the share price of each fund as `load_fund_prices`, and the sale date of each fund
+/// with a date in the optional Date column. See `SellOptions::sale_dates`. A fund may be listed
+/// more than once only with the same date each time.
+pub fn load_fund_prices_and_dates(
+    filename: &str,
+    options: &InputOptions,
+) -> Result<(HashMap<String, f64>, SaleDates), Error> {
+    let mut fund_prices: HashMap<String, Vec<(Option<chrono::NaiveTime>, f64)>> = HashMap::new();
+    let mut sale_dates = SaleDates::new();
+    let mut rdr = open_csv(filename, options)?;
+    let headers = rdr.headers().map_err(|err| Error::from(err).in_file(filename))?.clone();
+    let date_column = headers.iter().position(|h| h == "Date");
+    let amount_columns = column_indices(&headers, &["Share price"]);
+
+    for result in rdr.records() {
+        let fp = result
+            .map_err(Error::from)
+            .and_then(|row| {
+                let row = options.normalize_amounts(options.normalize_date(row, date_column)?, &amount_columns);
+                row.deserialize::<FundPrice>(Some(&headers)).map_err(Error::from)
+            })
+            .map_err(|err| err.in_file(filename))?;
+        if fp.share_price.is_nan() || fp.share_price <= 0.0 {
+            let err = AccountError::InvalidPrice {
+                fund: fp.fund,
+                price: fp.share_price,
+            };
+            return Err(Error::from(err).in_file(filename));
+        }
+        if let Some(prices) = fund_prices.get(&fp.fund) {
+            let conflict = prices.iter().find(|&&(time, price)| time == fp.time && price != fp.share_price);
+            if let Some(&(_, share_price)) = conflict {
+                let err = AccountError::DuplicatePrice {
+                    fund: fp.fund,
+                    first: share_price,
+                    second: fp.share_price,
+                };
+                return Err(Error::from(err).in_file(filename));
+            }
+            let first = sale_dates.get(&fp.fund).cloned();
+            if first != fp.date {
+                let err = AccountError::DuplicateSaleDate {
+                    fund: fp.fund,
+                    first,
+                    second: fp.date,
+                };
+                return Err(Error::from(err).in_file(filename));
+            }
+        }
+        trace!("{}: {:?}", filename, fp);
+        if let Some(date) = fp.date {
+            sale_dates.insert(fp.fund.clone(), date);
+        }
+        fund_prices.entry(fp.fund).or_default().push((fp.time, fp.share_price));
+    }
+
+    let fund_prices: HashMap<String, f64> = fund_prices
+        .into_iter()
+        .filter_map(|(fund, prices)| options.price_time.select(&prices).map(|price| (fund, price)))
+        .collect();
+    debug!("read the prices of {} funds from {}", fund_prices.len(), filename);
+    Ok((fund_prices, sale_dates))
+}
+
+/// Reads the share price of each fund on `date` from a CSV file of price history with the fields
+/// Fund, Date and Share price, e.g. to reproduce a past sale. A fund without a price on `date`
+/// has the price of the latest earlier date, and a fund with only later prices is left out, so
+/// selling it fails with `AccountError::MissingPrice`. A fund may be listed more than once on a
+/// date only with the same price each time.
+pub fn load_fund_prices_on(
+    filename: &str,
+    date: chrono::NaiveDate,
+    options: &InputOptions,
+) -> Result<HashMap<String, f64>, Error> {
+    let mut prices: HashMap<String, (chrono::NaiveDate, f64)> = HashMap::new();
+    let mut rdr = open_csv(filename, options)?;
+    let headers = rdr.headers().map_err(|err| Error::from(err).in_file(filename))?.clone();
+    let date_column = headers.iter().position(|h| h == "Date");
+    let amount_columns = column_indices(&headers, &["Share price"]);
+
+    for result in rdr.records() {
+        let hp = result
+            .map_err(Error::from)
+            .and_then(|row| {
+                let row = options.normalize_amounts(options.normalize_date(row, date_column)?, &amount_columns);
+                row.deserialize::<HistoricalPrice>(Some(&headers)).map_err(Error::from)
+            })
+            .map_err(|err| err.in_file(filename))?;
+        if hp.share_price.is_nan() || hp.share_price <= 0.0 {
+            let err = AccountError::InvalidPrice {
+                fund: hp.fund,
+                price: hp.share_price,
+            };
+            return Err(Error::from(err).in_file(filename));
+        }
+        if hp.date > date {
+            continue;
+        }
+        match prices.get(&hp.fund) {
+            Some(&(latest, share_price)) if latest == hp.date && share_price != hp.share_price => {
+                let err = AccountError::DuplicatePrice {
+                    fund: hp.fund,
+                    first: share_price,
+                    second: hp.share_price,
+                };
+                return Err(Error::from(err).in_file(filename));
+            }
+            Some(&(latest, _)) if latest >= hp.date => continue,
+            _ => {}
+        }
+        trace!("{}: {:?}", filename, hp);
+        prices.insert(hp.fund, (hp.date, hp.share_price));
+    }
+    debug!("read the prices on {} of {} funds from {}", date, prices.len(), filename);
+
+    Ok(prices.into_iter().map(|(fund, (_, share_price))| (fund, share_price)).collect())
+}
+
+/// Reads fund aliases from a CSV file with the fields Alias and Fund, mapping each alias to its
+/// fund. See `Account::with_fund_aliases`.
+pub fn load_fund_aliases(filename: &str, options: &InputOptions) -> Result<HashMap<String, String>, Error> {
+    let mut rdr = open_csv(filename, options)?;
+    let mut aliases = HashMap::new();
+    for result in rdr.deserialize() {
+        let alias: FundAlias = result.map_err(|err| Error::from(err).in_file(filename))?;
+        aliases.insert(alias.alias, alias.fund);
+    }
+    Ok(aliases)
+}
+
+/// Reads the stated balances of funds from a CSV file with the field Fund and either or both of
+/// the fields Shares and Value. See `Balance`.
+pub fn load_balances(filename: &str, options: &InputOptions) -> Result<Vec<Balance>, Error> {
+    let mut rdr = open_csv(filename, options)?;
+    let headers = rdr.headers().map_err(|err| Error::from(err).in_file(filename))?.clone();
+    let amount_columns = column_indices(&headers, &["Value"]);
+    let mut balances = Vec::new();
+    for result in rdr.records() {
+        let balance = result
+            .map_err(Error::from)
+            .and_then(|row| {
+                let row = options.normalize_amounts(row, &amount_columns);
+                row.deserialize::<Balance>(Some(&headers)).map_err(Error::from)
+            })
+            .map_err(|err| err.in_file(filename))?;
+        balances.push(balance);
+    }
+    Ok(balances)
+}
+
+/// Reads stock splits from a CSV file with the fields Fund, Date and Ratio. See `Split`.
+pub fn load_splits(filename: &str, options: &InputOptions) -> Result<Vec<Split>, Error> {
+    let mut rdr = open_csv(filename, options)?;
+    let headers = rdr.headers().map_err(|err| Error::from(err).in_file(filename))?.clone();
+    let date_column = headers.iter().position(|h| h == "Date");
+    let mut splits = Vec::new();
+    for result in rdr.records() {
+        let split = result
+            .map_err(Error::from)
+            .and_then(|row| {
+                let row = options.normalize_date(row, date_column)?;
+                row.deserialize::<Split>(Some(&headers)).map_err(Error::from)
+            })
+            .map_err(|err| err.in_file(filename))?;
+        splits.push(split);
+    }
+    Ok(splits)
+}
+
+/// Reads tax brackets from a CSV file with the fields Threshold and Rate, where Rate is the
+/// marginal rate of income above Threshold. `income` is the ordinary income the capital gains
+/// stack on.
+pub fn load_tax_brackets(filename: &str, income: f64, options: &InputOptions) -> Result<TaxBrackets, Error> {
+    let mut rdr = open_csv(filename, options)?;
+    let headers = rdr.headers().map_err(|err| Error::from(err).in_file(filename))?.clone();
+    let amount_columns = column_indices(&headers, &["Threshold", "Rate"]);
+    let mut brackets = Vec::new();
+    for result in rdr.records() {
+        let bracket = result
+            .and_then(|row| {
+                let row = options.normalize_amounts(row, &amount_columns);
+                row.deserialize::<TaxBracket>(Some(&headers))
+            })
+            .map_err(|err| Error::from(err).in_file(filename))?;
+        brackets.push((bracket.threshold, bracket.rate));
+    }
+    Ok(TaxBrackets::new(income, brackets))
+}
+
+/// Merges sell records of the same fund purchased on the same date at the same price into a
+/// single record. The capital gains of a merged record are recomputed from its totals.
+pub fn merge_lots(sell_records: Vec<SellRecord>) -> Vec<SellRecord> {
+    let mut indices: HashMap<_, usize> = HashMap::new();
+    let mut merged: Vec<SellRecord> = Vec::new();
+    for srec in sell_records {
+        let key = (srec.fund, srec.date_purchased, srec.share_price_purchased.to_bits());
+        match indices.get(&key) {
+            Some(&i) => {
+                let m = &mut merged[i];
+                m.num_shares += srec.num_shares;
+                m.amount += srec.amount;
+                m.sale_fee += srec.sale_fee;
+                let cap_gains = Cents::from_dollars((m.share_price - m.share_price_purchased) * m.num_shares);
+                m.cap_gains = cap_gains - m.sale_fee;
+                m.cap_gains_ratio = m.cap_gains.to_dollars() / m.amount.to_dollars();
+                m.net_amount += srec.net_amount;
+            }
+            None => {
+                indices.insert(key, merged.len());
+                merged.push(srec);
+            }
+        }
+    }
+
+    merged
+}
+
+/// Column by which the records of a sell summary are sorted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortKey {
+    Date,
+    Fund,
+    Amount,
+    CapGains,
+    CapGainsRatio,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "date" => Ok(SortKey::Date),
+            "fund" => Ok(SortKey::Fund),
+            "amount" => Ok(SortKey::Amount),
+            "cap-gains" => Ok(SortKey::CapGains),
+            "cg-ratio" => Ok(SortKey::CapGainsRatio),
+            _ => Err("expected one of date, fund, amount, cap-gains, cg-ratio".to_string()),
+        }
+    }
+}
+
+/// Options controlling how sell summaries and reports are printed.
+#[derive(Clone, Debug)]
+pub struct OutputOptions {
+    /// Number of decimal places printed for dollar amounts. Share counts and ratios are not
+    /// affected.
+    pub precision: usize,
+    /// Column the records of a sell summary are sorted by. Ties keep the order of the sale.
+    pub sort_by: SortKey,
+    pub descending: bool,
+    /// Currency whose symbol is printed with the totals.
+    pub currency: Currency,
+    /// Kind of the sell target the proceeds of a sale are compared with.
+    pub target_kind: TargetKind,
+    /// Print the shares, amount and capital gains sold of each fund instead of each record sold.
+    pub by_fund: bool,
+    /// Leave out notes on the sale, such as that it realizes a net loss.
+    pub quiet: bool,
+    /// Write dollar amounts with the currency symbol and the thousands and decimal separators of
+    /// the currency, e.g. $123,456.78, in tables as well as totals. Plain numbers are easier to
+    /// parse, so this is off by default.
+    pub grouped: bool,
+    /// Print the row of the account file each record sold was read from, to find it in the file.
+    pub show_rows: bool,
+    /// Print the annualized return of each record sold. See `SellRecord::annualized_return`.
+    pub show_annualized: bool,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        OutputOptions {
+            precision: 2,
+            sort_by: SortKey::Date,
+            descending: true,
+            currency: Currency::default(),
+            target_kind: TargetKind::default(),
+            by_fund: false,
+            quiet: false,
+            grouped: false,
+            show_rows: false,
+            show_annualized: false,
+        }
+    }
+}
+
+impl OutputOptions {
+    /// Formats `amount` for the totals, with the currency symbol.
+    fn money(&self, amount: f64) -> String {
+        if self.grouped {
+            self.currency.format_grouped(amount, self.precision)
+        } else {
+            self.currency.format(amount, self.precision)
+        }
+    }
+
+    /// Formats `amount` for a column of a table, as a plain number unless `grouped`.
+    fn column(&self, amount: f64) -> String {
+        if self.grouped {
+            self.currency.format_grouped(amount, self.precision)
+        } else {
+            format!("{:.*}", self.precision, amount)
+        }
+    }
+
+    fn sort(&self, summary: &mut [SellRecord]) {
+        summary.sort_by(|a, b| {
+            let ordering = match self.sort_by {
+                SortKey::Date => a.date_purchased.cmp(&b.date_purchased),
+                SortKey::Fund => a.fund.cmp(b.fund),
+                SortKey::Amount => a.amount.cmp(&b.amount),
+                SortKey::CapGains => a.cap_gains.cmp(&b.cap_gains),
+                SortKey::CapGainsRatio => a.cap_gains_ratio.total_cmp(&b.cap_gains_ratio),
+            };
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+}
+
+/// Prints the records in the order they are considered for selling, e.g. as ranked by
+/// `Account::ranked_sell_records`, so the choice of records can be followed.
+pub fn print_candidates(sell_records: &[SellRecord], options: &OutputOptions) {
+    println!("Considering the following records, in order:");
+
+    let show_account = sell_records.iter().any(|s| s.account.is_some());
+    if show_account {
+        print!("  {:>25},", "account");
+    }
+    println!(
+        "  {:>4}, {:>10}, {:>25}, {:>10}, {:>10}, {:>9}",
+        "rank", "date", "fund", "amount", "cap gains", "cg ratio"
+    );
+    for (i, srec) in sell_records.iter().enumerate() {
+        if show_account {
+            print!("  {:>25},", srec.account.as_ref().map_or("", |s| s.as_str()));
+        }
+        println!(
+            "  {:>4}, {}, {:>25}, {:>10}, {:>10}, {:9.4}",
+            i + 1,
+            srec.date_purchased,
+            srec.fund,
+            options.column(srec.amount.to_dollars()),
+            options.column(srec.cap_gains.to_dollars()),
+            srec.cap_gains_ratio
+        );
+    }
+    println!();
+}
+
+/// Prints how the amount, capital gains, capital gains ratio and taxes of selling a record are
+/// computed, step by step.
+pub fn print_explanation(srec: &SellRecord, tax_rates: &TaxRates, options: &OutputOptions) {
+    let mut s = String::new();
+    write_explanation(&mut s, srec, tax_rates, options).unwrap();
+    print!("{}", s);
+}
+
+/// Writes the explanation printed by `print_explanation` to `w`.
+pub fn write_explanation<W: fmt::Write>(
+    w: &mut W,
+    srec: &SellRecord,
+    tax_rates: &TaxRates,
+    options: &OutputOptions,
+) -> fmt::Result {
+    let money = |amount: f64| options.money(amount);
+    let basis = srec.share_price_purchased * srec.num_shares;
+    writeln!(
+        w,
+        "Selling the record of {} purchased on {}, on {}:",
+        srec.fund, srec.date_purchased, srec.sale_date
+    )?;
+    writeln!(w, "  shares:         {}", srec.num_shares)?;
+    writeln!(w, "  purchase price: {} per share, including fees", money(srec.share_price_purchased))?;
+    writeln!(w, "  current price:  {} per share", money(srec.share_price))?;
+    let price = money(srec.share_price);
+    if srec.sale_fee != Cents::ZERO {
+        let (fee, amount) = (money(srec.sale_fee.to_dollars()), money(srec.amount.to_dollars()));
+        writeln!(w, "  amount:         {} shares * {} - {} fee = {}", srec.num_shares, price, fee, amount)?;
+    } else {
+        let amount = money(srec.amount.to_dollars());
+        writeln!(w, "  amount:         {} shares * {} = {}", srec.num_shares, price, amount)?;
+    }
+    let price = money(srec.share_price_purchased);
+    writeln!(w, "  cost basis:     {} shares * {} = {}", srec.num_shares, price, money(basis))?;
+    writeln!(
+        w,
+        "  cap gains:      {} - {} = {}",
+        money(srec.amount.to_dollars()),
+        money(basis),
+        money(srec.cap_gains.to_dollars())
+    )?;
+    writeln!(
+        w,
+        "  cg ratio:       {} / {} = {:.4}",
+        money(srec.cap_gains.to_dollars()),
+        money(srec.amount.to_dollars()),
+        srec.cap_gains_ratio
+    )?;
+    let term = if srec.is_long_term { "long" } else { "short" };
+    writeln!(w, "  held:           {} days, {} term", srec.holding_days, term)?;
+    if !srec.taxable {
+        writeln!(w, "  taxes:          none, the account is tax-free")?;
+    } else {
+        let rate = tax_rates.rate(srec);
+        let taxes = tax_rates.taxes(srec);
+        let cap_gains = money(srec.cap_gains.to_dollars());
+        writeln!(w, "  taxes:          {} * {:.2}% = {}", cap_gains, 100.0 * rate, money(taxes))?;
+    }
+    writeln!(
+        w,
+        "  net amount:     {} - {} = {}",
+        money(srec.amount.to_dollars()),
+        money(srec.amount.to_dollars() - srec.net_amount),
+        money(srec.net_amount)
+    )
+}
+
+/// Asks on `output` whether to sell each record of `summary`, reading y or n answers from
+/// `input`, and prints the proceeds of the records confirmed so far against `sell_target`.
+/// Returns the summary of the confirmed records, with the declined records added to the
+/// remaining records. Running out of input before every record is answered is an error.
+pub fn confirm_records<'a, R: io::BufRead, W: io::Write>(
+    summary: SellSummary<'a>,
+    sell_target: f64,
+    tax_rates: &TaxRates,
+    options: &OutputOptions,
+    mut input: R,
+    mut output: W,
+) -> io::Result<SellSummary<'a>> {
+    let money = |amount: f64| options.money(amount);
+    let mut confirmed = Vec::new();
+    let mut declined = Vec::new();
+    for srec in summary.records {
+        let sell = loop {
+            write!(
+                output,
+                "{}, {}, {} shares, amount {}, cap gains {}. Sell? [y/n] ",
+                srec.date_purchased,
+                srec.fund,
+                srec.num_shares,
+                money(srec.amount.to_dollars()),
+                money(srec.cap_gains.to_dollars())
+            )?;
+            output.flush()?;
+            let mut answer = String::new();
+            if input.read_line(&mut answer)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "no answer for every record"));
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => break true,
+                "n" | "no" => break false,
+                _ => writeln!(output, "Please answer y or n.")?,
+            }
+        };
+        if sell {
+            confirmed.push(srec);
+        } else {
+            declined.push(srec);
+        }
+
+        let running = SellSummary::new(confirmed.clone(), tax_rates);
+        let proceeds = options.target_kind.proceeds(running.total_amount, running.total_tax);
+        writeln!(output, "  total: {} of the {} target", money(proceeds), money(sell_target))?;
+    }
+    writeln!(output)?;
+
+    declined.extend(summary.remaining);
+    Ok(SellSummary::new(confirmed, tax_rates).with_remaining(declined))
+}
+
+pub fn print_sell_summary(
+    summary: SellSummary,
+    sell_target: Option<f64>,
+    tax_rates: &TaxRates,
+    options: &OutputOptions,
+) {
+    let mut s = String::new();
+    write_sell_summary(&mut s, summary, sell_target, tax_rates, options).unwrap();
+    print!("{}", s);
+}
+
+/// Writes the sell summary printed by `print_sell_summary` to `w`.
+pub fn write_sell_summary<W: fmt::Write>(
+    w: &mut W,
+    mut summary: SellSummary,
+    sell_target: Option<f64>,
+    tax_rates: &TaxRates,
+    options: &OutputOptions,
+) -> fmt::Result {
+    options.sort(&mut summary.records);
+    let sold = if options.by_fund { "shares of each fund" } else { "records" };
+    match summary.records.first() {
+        Some(srec) => writeln!(w, "Selling the following {} on {}:", sold, srec.sale_date)?,
+        None => writeln!(w, "Selling the following {}:", sold)?,
+    }
+    if options.by_fund {
+        write_fund_summaries(w, &summary.records, options)?;
+    } else {
+        write_records(w, &summary.records, tax_rates, options)?;
+    }
+    for srec in &summary.records {
+        if let Some(date) = srec.wash_sale {
+            writeln!(
+                w,
+                "warning: {} was also acquired {}, the loss on the record purchased {} may be a wash sale",
+                srec.fund, date, srec.date_purchased
+            )?;
+        }
+    }
+
+    writeln!(w, "will result in")?;
+    write_totals(w, &summary, tax_rates, options)?;
+    // a net loss is what harvesting losses is for, but otherwise likely a mistake
+    if summary.total_cap_gains < 0.0 && !options.quiet {
+        let loss = options.money(-summary.total_cap_gains);
+        writeln!(w)?;
+        writeln!(w, "note: this sale realizes a net capital loss of {}.", loss)?;
+        writeln!(w, "      Check that this is intended, e.g. to harvest the loss.")?;
+    }
+
+    // records are sold whole or in whole shares, so the net amount can exceed the target, or
+    // fall short of it when only selling whole records
+    if let Some(sell_target) = sell_target {
+        let money = |amount: f64| options.money(amount);
+        let achieved = options.target_kind.proceeds(summary.total_amount, summary.total_tax);
+        writeln!(w)?;
+        writeln!(w, "target:     {:>11}", money(sell_target))?;
+        writeln!(w, "achieved:   {:>11}", money(achieved))?;
+        if achieved < sell_target {
+            writeln!(w, "undershoot: {:>11}", money(sell_target - achieved))?;
+        } else {
+            writeln!(w, "overshoot:  {:>11}", money(achieved - sell_target))?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints the sales of each year of `split`, labeled by year, and the totals of both years.
+pub fn print_year_split(split: YearSplit, sell_target: f64, tax_rates: &TaxRates, options: &OutputOptions) {
+    let mut s = String::new();
+    write_year_split(&mut s, split, sell_target, tax_rates, options).unwrap();
+    print!("{}", s);
+}
+
+/// Writes the sales printed by `print_year_split` to `w`.
+pub fn write_year_split<W: fmt::Write>(
+    w: &mut W,
+    split: YearSplit,
+    sell_target: f64,
+    tax_rates: &TaxRates,
+    options: &OutputOptions,
+) -> fmt::Result {
+    let second = match split.second {
+        Some(second) => second,
+        None => {
+            writeln!(w, "This year, the target is reached within the capital gains limit.")?;
+            return write_sell_summary(w, split.first, Some(sell_target), tax_rates, options);
+        }
+    };
+
+    let proceeds = |summary: &SellSummary| options.target_kind.proceeds(summary.total_amount, summary.total_tax);
+    let first_proceeds = proceeds(&split.first);
+    let totals = (
+        split.first.total_amount + second.total_amount,
+        split.first.total_cap_gains + second.total_cap_gains,
+        split.first.total_tax + second.total_tax,
+        first_proceeds + proceeds(&second),
+    );
+    writeln!(w, "This year, up to the capital gains limit:")?;
+    write_sell_summary(w, split.first, None, tax_rates, options)?;
+    writeln!(w)?;
+    writeln!(w, "Next year, the rest of the target:")?;
+    write_sell_summary(w, second, Some(sell_target - first_proceeds), tax_rates, options)?;
+
+    let (amount, cap_gains, taxes, achieved) = totals;
+    writeln!(w)?;
+    writeln!(w, "both years:")?;
+    writeln!(w, "amount:     {:>11}", options.money(amount))?;
+    writeln!(w, "cap gains:  {:>11}", options.money(cap_gains))?;
+    if !tax_rates.is_zero() {
+        writeln!(w, "taxes:      {:>11}", options.money(taxes))?;
+    }
+    writeln!(w, "target:     {:>11}", options.money(sell_target))?;
+    writeln!(w, "achieved:   {:>11}", options.money(achieved))?;
+    Ok(())
+}
+
+/// Writes the table of the records sold of `write_sell_summary`, with a row per record.
+fn write_records<W: fmt::Write>(
+    w: &mut W,
+    records: &[SellRecord],
+    tax_rates: &TaxRates,
+    options: &OutputOptions,
+) -> fmt::Result {
+    // only show the account column when selling from several accounts
+    let show_account = records.iter().any(|s| s.account.is_some());
+    if show_account {
+        write!(w, "  {:>25},", "account")?;
+    }
+    if options.show_rows {
+        write!(w, "  {:>6},", "row")?;
+    }
+    // the taxes of each record are only shown when there are any
+    let show_tax = !tax_rates.is_zero();
+    write!(w, "  {:>10}, {:>25}, {:>10}, {:>10},", "date", "fund", "amount", "cap gains")?;
+    if show_tax {
+        write!(w, " {:>10},", "tax")?;
+    }
+    write!(w, " {:>6}, {:>5},", "days", "term")?;
+    if options.show_annualized {
+        write!(w, " {:>10},", "annualized")?;
+    }
+    writeln!(w, " {:>10}", "shares")?;
+    for srec in records {
+        if show_account {
+            write!(w, "  {:>25},", srec.account.as_ref().map_or("", |s| s.as_str()))?;
+        }
+        if options.show_rows {
+            write!(w, "  {:>6},", srec.source_row.map_or(String::new(), |row| row.to_string()))?;
+        }
+        // print out when selling a whole number of shares as it's not too common
+        let shares = if srec.num_shares.fract() == 0.0 {
+            format!("{:>10} [whole]", srec.num_shares)
+        } else {
+            format!("{:10.3}", srec.num_shares)
+        };
+        let term = if srec.is_long_term { "long" } else { "short" };
+        write!(
+            w,
+            "  {}, {:>25}, {:>10}, {:>10},",
+            srec.date_purchased,
+            srec.fund,
+            options.column(srec.amount.to_dollars()),
+            options.column(srec.cap_gains.to_dollars())
+        )?;
+        if show_tax {
+            write!(w, " {:>10},", options.column(tax_rates.taxes(srec)))?;
+        }
+        write!(w, " {:>6}, {:>5},", srec.holding_days, term)?;
+        if options.show_annualized {
+            let annualized = srec.annualized_return().map_or(String::new(), |r| format!("{:.2}%", 100.0*r));
+            write!(w, " {:>10},", annualized)?;
+        }
+        writeln!(w, " {}", shares)?;
+    }
+    Ok(())
+}
+
+fn write_totals<W: fmt::Write>(
+    w: &mut W,
+    summary: &SellSummary,
+    tax_rates: &TaxRates,
+    options: &OutputOptions,
+) -> fmt::Result {
+    let money = |amount: f64| options.money(amount);
+    writeln!(w, "amount:     {:>11}", money(summary.total_amount))?;
+    writeln!(w, "cap gains:  {:>11}", money(summary.total_cap_gains))?;
+    writeln!(w, "  long:     {:>11}", money(summary.long_term_cap_gains))?;
+    let short_term = Cents::from_dollars(summary.total_cap_gains) - Cents::from_dollars(summary.long_term_cap_gains);
+    writeln!(w, "  short:    {:>11}", money(short_term.to_dollars()))?;
+    if tax_rates.carryover_loss > 0.0 {
+        writeln!(w, "carryover:  {:>11}", money(-summary.carryover_applied))?;
+    }
+    if !tax_rates.is_zero() {
+        writeln!(w, "taxes:      {:>11}", money(summary.total_tax))?;
+        writeln!(w, "net amount: {:>11}", money(summary.net_amount))?;
+    }
+
+    // the records of share classes of the same fund are also totaled together
+    let mut groups: BTreeMap<&str, (Cents, Cents)> = BTreeMap::new();
+    for srec in &summary.records {
+        if let Some(ref group) = srec.fund_group {
+            let totals = groups.entry(group).or_default();
+            totals.0 += srec.amount;
+            totals.1 += srec.cap_gains;
+        }
+    }
+    for (group, (amount, cap_gains)) in groups {
+        writeln!(w, "{}: amount {}, cap gains {}", group, money(amount.to_dollars()), money(cap_gains.to_dollars()))?;
+    }
+    Ok(())
+}
+
+/// Totals over the sell records of a single fund.
+#[derive(Clone, Debug, Serialize)]
+pub struct FundSummary<'a> {
+    pub fund: &'a str,
+    pub num_shares: f64,
+    pub amount: f64,
+    pub cap_gains: f64,
+}
+
+/// Sums the shares, amount and capital gains of the sell records of each fund, with the amount
+/// and capital gains summed in cents like the totals of a `SellSummary`. The result is sorted by
+/// fund.
+pub fn summarize_by_fund<'a>(sell_records: &[SellRecord<'a>]) -> Vec<FundSummary<'a>> {
+    let mut totals: BTreeMap<&str, (f64, Cents, Cents)> = BTreeMap::new();
+    for srec in sell_records {
+        let totals = totals.entry(srec.fund).or_default();
+        totals.0 += srec.num_shares;
+        totals.1 += srec.amount;
+        totals.2 += srec.cap_gains;
+    }
+
+    totals
+        .into_iter()
+        .map(|(fund, (num_shares, amount, cap_gains))| FundSummary {
+            fund,
+            num_shares,
+            amount: amount.to_dollars(),
+            cap_gains: cap_gains.to_dollars(),
+        })
+        .collect()
+}
+
+/// Prints the unrealized capital gains of selling every record, with subtotals for each fund.
+pub fn print_report(sell_records: &[SellRecord], tax_rates: &TaxRates, options: &OutputOptions) {
+    println!("Unrealized capital gains by fund:");
+    print_fund_summaries(sell_records, options);
+
+    println!("selling everything would result in");
+    let summary = SellSummary::new(sell_records.to_vec(), tax_rates);
+    let mut totals = String::new();
+    write_totals(&mut totals, &summary, tax_rates, options).unwrap();
+    print!("{}", totals);
+}
+
+/// What a stated balance of a fund is of.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BalanceKind {
+    Shares,
+    Value,
+}
+
+impl fmt::Display for BalanceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BalanceKind::Shares => f.pad("shares"),
+            BalanceKind::Value => f.pad("value"),
+        }
+    }
+}
+
+/// A stated balance of a fund compared with the shares or value of its sell records.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BalanceCheck {
+    pub fund: String,
+    pub kind: BalanceKind,
+    pub stated: f64,
+    pub computed: f64,
+}
+
+impl BalanceCheck {
+    /// Returns how much the records hold more than stated, negative if they hold less.
+    pub fn delta(&self) -> f64 {
+        self.computed - self.stated
+    }
+
+    /// Returns whether the records match the stated balance within `tolerance`.
+    pub fn matches(&self, tolerance: f64) -> bool {
+        self.delta().abs() <= tolerance
+    }
+}
+
+/// Compares each of the shares and value stated by `balances` with the sum of the sell records of
+/// the fund, in the order of `balances`. A fund without records holds nothing.
+pub fn reconcile(sell_records: &[SellRecord], balances: &[Balance]) -> Vec<BalanceCheck> {
+    let summaries = summarize_by_fund(sell_records);
+    let mut checks = Vec::new();
+    for balance in balances {
+        let summary = summaries.iter().find(|summary| summary.fund == balance.fund);
+        let stated = [(BalanceKind::Shares, balance.shares), (BalanceKind::Value, balance.value)];
+        for &(kind, stated) in &stated {
+            if let Some(stated) = stated {
+                let computed = summary.map_or(0.0, |summary| match kind {
+                    BalanceKind::Shares => summary.num_shares,
+                    BalanceKind::Value => summary.amount,
+                });
+                checks.push(BalanceCheck {
+                    fund: balance.fund.clone(),
+                    kind,
+                    stated,
+                    computed,
+                });
+            }
+        }
+    }
+    checks
+}
+
+/// Prints each balance check with its delta and whether it passes within `tolerance`.
+pub fn print_reconciliation(checks: &[BalanceCheck], tolerance: f64, options: &OutputOptions) {
+    let mut s = String::new();
+    write_reconciliation(&mut s, checks, tolerance, options).unwrap();
+    print!("{}", s);
+}
+
+/// Writes the report printed by `print_reconciliation` to `w`.
+pub fn write_reconciliation<W: fmt::Write>(
+    w: &mut W,
+    checks: &[BalanceCheck],
+    tolerance: f64,
+    options: &OutputOptions,
+) -> fmt::Result {
+    writeln!(
+        w,
+        "  {:>25}, {:>8}, {:>12}, {:>12}, {:>10}, {:>6}",
+        "fund", "balance", "stated", "computed", "delta", "result"
+    )?;
+    for check in checks {
+        // shares are counted in thousandths, as they are bought
+        let number = |n: f64| match check.kind {
+            BalanceKind::Shares => format!("{:.3}", n),
+            BalanceKind::Value => options.column(n),
+        };
+        let result = if check.matches(tolerance) { "pass" } else { "FAIL" };
+        writeln!(
+            w,
+            "  {:>25}, {:>8}, {:>12}, {:>12}, {:>10}, {:>6}",
+            check.fund,
+            check.kind,
+            number(check.stated),
+            number(check.computed),
+            number(check.delta()),
+            result
+        )?;
+    }
+    Ok(())
+}
+
+/// Prints the shares held, average cost, price and unrealized capital gains of each fund, with
+/// the funds with the most gains first.
+pub fn print_fund_list(sell_records: &[SellRecord], options: &OutputOptions) {
+    println!("Holdings by fund:");
+    let mut s = String::new();
+    write_fund_list(&mut s, sell_records, options).unwrap();
+    print!("{}", s);
+}
+
+/// Writes the list of funds printed by `print_fund_list` to `w`.
+pub fn write_fund_list<W: fmt::Write>(w: &mut W, sell_records: &[SellRecord], options: &OutputOptions) -> fmt::Result {
+    let mut summaries = summarize_by_fund(sell_records);
+    // summaries are sorted by fund, which breaks ties
+    summaries.sort_by(|a, b| b.cap_gains.partial_cmp(&a.cap_gains).unwrap_or(Ordering::Equal));
+
+    writeln!(
+        w,
+        "  {:>25}, {:>10}, {:>10}, {:>10}, {:>10}",
+        "fund", "shares", "avg cost", "price", "cap gains"
+    )?;
+    for summary in summaries {
+        let cost = summary.amount - summary.cap_gains;
+        writeln!(
+            w,
+            "  {:>25}, {:10.3}, {:>10}, {:>10}, {:>10}",
+            summary.fund,
+            summary.num_shares,
+            options.column(cost / summary.num_shares),
+            options.column(summary.amount / summary.num_shares),
+            options.column(summary.cap_gains)
+        )?;
+    }
+    Ok(())
+}
+
+/// Prints the shares held and unrealized capital gains of each fund after a sale, from the
+/// records that are not sold.
+pub fn print_remaining(remaining: &[SellRecord], options: &OutputOptions) {
+    let mut s = String::new();
+    write_remaining(&mut s, remaining, options).unwrap();
+    print!("{}", s);
+}
+
+/// Writes the holdings printed by `print_remaining` to `w`.
+pub fn write_remaining<W: fmt::Write>(w: &mut W, remaining: &[SellRecord], options: &OutputOptions) -> fmt::Result {
+    writeln!(w, "Remaining holdings by fund:")?;
+    write_fund_summaries(w, remaining, options)
+}
+
+fn print_fund_summaries(sell_records: &[SellRecord], options: &OutputOptions) {
+    let mut s = String::new();
+    write_fund_summaries(&mut s, sell_records, options).unwrap();
+    print!("{}", s);
+}
+
+fn write_fund_summaries<W: fmt::Write>(
+    w: &mut W,
+    sell_records: &[SellRecord],
+    options: &OutputOptions,
+) -> fmt::Result {
+    writeln!(w, "  {:>25}, {:>10}, {:>10}, {:>10}", "fund", "shares", "amount", "cap gains")?;
+    for summary in summarize_by_fund(sell_records) {
+        writeln!(
+            w,
+            "  {:>25}, {:10.3}, {:>10}, {:>10}",
+            summary.fund,
+            summary.num_shares,
+            options.column(summary.amount),
+            options.column(summary.cap_gains)
+        )?;
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct JsonSummary<'a> {
+    records: &'a [SellRecord<'a>],
+    amount: f64,
+    cap_gains: f64,
+    carryover_applied: f64,
+    taxes: f64,
+    net_amount: f64,
+    target: Option<f64>,
+    overshoot: Option<f64>,
+}
+
+/// Prints the sell summary as a JSON object containing the records sold, the totals and how far
+/// the net amount exceeds the sell target, if there is one.
+pub fn print_sell_summary_json(
+    summary: SellSummary,
+    sell_target: Option<f64>,
+    options: &OutputOptions,
+) -> Result<(), Error> {
+    write_sell_summary_json(io::stdout(), summary, sell_target, options)
+}
+
+/// Writes the JSON object printed by `print_sell_summary_json` to `w`.
+pub fn write_sell_summary_json<W: io::Write>(
+    mut w: W,
+    mut summary: SellSummary,
+    sell_target: Option<f64>,
+    options: &OutputOptions,
+) -> Result<(), Error> {
+    options.sort(&mut summary.records);
+
+    let json = json::to_string(&JsonSummary {
+        records: &summary.records,
+        amount: summary.total_amount,
+        cap_gains: summary.total_cap_gains,
+        carryover_applied: summary.carryover_applied,
+        taxes: summary.total_tax,
+        net_amount: summary.net_amount,
+        target: sell_target,
+        overshoot: sell_target
+            .map(|target| options.target_kind.proceeds(summary.total_amount, summary.total_tax) - target),
+    })?;
+    writeln!(w, "{}", json).map_err(csv::Error::from)?;
+    Ok(())
+}
+
+/// Returns the totals of the sale as a single line of `key=value` fields, such as
+/// `status=ok amount=500.00 cap_gains=50.00 tax=10.00 net=490.00`, for scripts to read instead of
+/// the output. Amounts are plain numbers with cents, whatever the output options.
+pub fn exit_summary(summary: &SellSummary) -> String {
+    format!(
+        "status=ok amount={:.2} cap_gains={:.2} tax={:.2} net={:.2}",
+        summary.total_amount, summary.total_cap_gains, summary.total_tax, summary.net_amount
+    )
+}
+
+/// Prints the records sold as CSV, with a header row and without the totals, so the output can
+/// be imported into a spreadsheet.
+pub fn print_sell_summary_csv(summary: SellSummary, options: &OutputOptions) -> Result<(), Error> {
+    write_sell_summary_csv(io::stdout(), summary, options)
+}
+
+/// Writes the records printed by `print_sell_summary_csv` to `w`.
+pub fn write_sell_summary_csv<W: io::Write>(
+    w: W,
+    mut summary: SellSummary,
+    options: &OutputOptions,
+) -> Result<(), Error> {
+    let p = options.precision;
+    options.sort(&mut summary.records);
+
+    let mut wtr = csv::Writer::from_writer(w);
+    wtr.write_record([
+        "date",
+        "fund",
+        "amount",
+        "cap gains",
+        "cg ratio",
+        "shares",
+        "net amount",
+        "wash sale",
+        "account",
+    ])?;
+    for srec in &summary.records {
+        wtr.write_record([
+            srec.date_purchased.to_string(),
+            srec.fund.to_string(),
+            format!("{:.*}", p, srec.amount),
+            format!("{:.*}", p, srec.cap_gains),
+            format!("{:.6}", srec.cap_gains_ratio),
+            srec.num_shares.to_string(),
+            format!("{:.*}", p, srec.net_amount),
+            srec.wash_sale.map_or(String::new(), |date| date.to_string()),
+            srec.account.clone().unwrap_or_default(),
+        ])?;
+    }
+    wtr.flush().map_err(csv::Error::from)?;
+    Ok(())
+}
+
+/// Prints the fund, purchase date and number of shares of each record sold as CSV without a
+/// header, as needed to sell specific lots with a broker. Shares are printed with full precision.
+/// The account is added when selling from several accounts.
+pub fn print_lot_list(summary: SellSummary, options: &OutputOptions) -> Result<(), Error> {
+    write_lot_list(io::stdout(), summary, options)
+}
+
+/// Writes the lots printed by `print_lot_list` to `w`.
+pub fn write_lot_list<W: io::Write>(w: W, mut summary: SellSummary, options: &OutputOptions) -> Result<(), Error> {
+    options.sort(&mut summary.records);
+
+    let mut wtr = csv::Writer::from_writer(w);
+    for srec in &summary.records {
+        let mut row = vec![srec.fund.to_string(), srec.date_purchased.to_string(), srec.num_shares.to_string()];
+        row.extend(srec.account.clone());
+        wtr.write_record(&row)?;
+    }
+    wtr.flush().map_err(csv::Error::from)?;
+    Ok(())
+}
@@ -0,0 +1,99 @@
+//! Leveled diagnostics on stderr, for debugging how records are read and selected. Nothing is
+//! logged until a level is set with `set_level`, so the normal output stays clean.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Importance of a message. Setting a level logs the messages of it and every more important
+/// level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            _ => Err("expected one of error, warn, info, debug, trace".to_string()),
+        }
+    }
+}
+
+// the least important level logged, or 0 to log nothing
+static LEVEL: AtomicUsize = AtomicUsize::new(0);
+
+/// Logs the messages of `level` and more important levels, or nothing for `None`.
+pub fn set_level(level: Option<Level>) {
+    LEVEL.store(level.map_or(0, |level| level as usize), Ordering::Relaxed);
+}
+
+/// Returns whether messages of `level` are logged.
+pub fn enabled(level: Level) -> bool {
+    level as usize <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// Returns the level of a `RUST_LOG` value, either a level, e.g. "debug", or a comma-separated
+/// list of directives such as "capgains=debug", where the last one wins. Directives for other
+/// crates are ignored.
+pub fn level_from_env(value: &str) -> Option<Level> {
+    value
+        .split(',')
+        .rev()
+        .find_map(|directive| match directive.find('=') {
+            Some(i) if directive[..i].trim() == "capgains" => directive[i + 1..].trim().parse().ok(),
+            Some(_) => None,
+            None => directive.trim().parse().ok(),
+        })
+}
+
+/// Writes a message to stderr if messages of `level` are logged.
+pub fn log(level: Level, args: fmt::Arguments) {
+    if enabled(level) {
+        eprintln!("[{:<5} capgains] {}", level, args);
+    }
+}
+
+/// Logs a message of debug level, formatted as with `format!`.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Debug) {
+            $crate::log::log($crate::log::Level::Debug, format_args!($($arg)*));
+        }
+    };
+}
+
+/// Logs a message of trace level, formatted as with `format!`.
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::Trace) {
+            $crate::log::log($crate::log::Level::Trace, format_args!($($arg)*));
+        }
+    };
+}
@@ -1,313 +1,1280 @@
-#![feature(map_get_key_value)]
+// serde_derive wraps its generated impls in a named const
+#![allow(non_local_definitions)]
 
+extern crate capgains;
 extern crate chrono;
-extern crate csv;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 
+mod config;
+
 use std::collections::HashMap;
-use std::collections::HashSet;
 use std::env;
 use std::error;
 use std::fmt;
+use std::fs;
+use std::io;
+use std::io::Write;
 use std::process;
 use std::str::FromStr;
 
-use serde::{de, Deserialize, Deserializer};
-
-#[derive(Clone, Debug, Deserialize)]
-struct Record {
-    #[serde(rename = "Date", deserialize_with = "de_date_from_str")]
-    date: chrono::NaiveDate,
-    #[serde(rename = "Fund")]
-    fund: String,
-    #[serde(rename = "Transaction type")]
-    transaction_type: String,
-    #[serde(rename = "Shares transacted")]
-    num_shares: f64,
-    #[serde(rename = "Share price", deserialize_with = "de_usd_from_str")]
-    share_price: f64,
-    #[serde(rename = "Amount", deserialize_with = "de_usd_from_str")]
-    amount: f64, // dependent field
-}
+use chrono::Datelike;
 
-#[derive(Clone, Debug)]
-struct SellRecord<'a> {
-    date_purchased: chrono::NaiveDate,
-    fund: &'a str,
-    num_shares: f64,
-    share_price_purchased: f64,
-    share_price: f64,
-    amount: f64,
-    cap_gains: f64,
-    cap_gains_ratio: f64,
+use capgains::{
+    confirm_records, exit_summary, load_accounts, load_balances, load_fund_aliases, load_fund_prices_and_dates,
+    load_fund_prices_on, load_splits, load_tax_brackets, merge_lots, print_candidates, print_explanation,
+    print_fund_list, print_reconciliation, print_report, print_year_split, reconcile, write_lot_list,
+    write_remaining, write_sell_summary, write_sell_summary_csv, write_sell_summary_json, AccountError, Currency,
+    FundFilter, InputOptions, log, OutputOptions, Portfolio, SellOptions, SellSummary, SortKey, TargetKind, TaxRates,
+    YearLimit, PRECISION_TOLERANCE, STDIN,
+};
+
+use config::Config;
+
+/// Strategy used to choose which records to sell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Method {
+    MinimumCapGains,
+    MinimumTax,
+    Fifo,
+    Lifo,
+    Hifo,
+    AverageCost,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-struct FundPrice {
-    #[serde(rename = "Fund")]
-    fund: String,
-    #[serde(rename = "Share price", deserialize_with = "de_usd_from_str")]
-    share_price: f64,
+impl Method {
+    fn description(&self) -> &'static str {
+        match *self {
+            Method::MinimumCapGains => "Minimizing capital gains",
+            Method::MinimumTax => "Minimizing taxes",
+            Method::Fifo => "Selling first-in, first-out",
+            Method::Lifo => "Selling last-in, first-out",
+            Method::Hifo => "Selling highest-in, first-out",
+            Method::AverageCost => "Selling with average-cost basis",
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
-struct AccountError(String);
+impl FromStr for Method {
+    type Err = String;
 
-impl fmt::Display for AccountError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mincg" => Ok(Method::MinimumCapGains),
+            "mintax" => Ok(Method::MinimumTax),
+            "fifo" => Ok(Method::Fifo),
+            "lifo" => Ok(Method::Lifo),
+            "hifo" => Ok(Method::Hifo),
+            "avgcost" => Ok(Method::AverageCost),
+            _ => Err("expected one of mincg, mintax, fifo, lifo, hifo, avgcost".to_string()),
+        }
     }
 }
 
-impl error::Error for AccountError {
-    fn description(&self) -> &str {
-        &self.0
+/// Output format of the sell summary.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Table,
+    Json,
+    Csv,
+    Lots,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "lots" => Ok(Format::Lots),
+            _ => Err("expected one of table, json, csv, lots".to_string()),
+        }
     }
 }
 
-fn de_date_from_str<'de, D>(deserializer: D) -> Result<chrono::NaiveDate, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s: &str = Deserialize::deserialize(deserializer)?;
-    chrono::NaiveDate::parse_from_str(s, "%m/%d/%Y").map_err(de::Error::custom)
+const USAGE: &str = "\
+Calculate the records to sell to minimize capital gains.
+
+usage: capgains --account <file> (--fund-prices <file> | --price <fund>=<price>...) --target <amount> [options]
+       capgains --account <file> (--fund-prices <file> | --price <fund>=<price>...) --target-shares <shares>
+                --fund <fund> [options]
+       capgains --account <file> (--fund-prices <file> | --price <fund>=<price>...) --target-cap-gains <amount>
+                [options]
+       capgains --account <file> (--fund-prices <file> | --price <fund>=<price>...) (--report | --list-funds)
+                [options]
+       capgains --account <file> (--fund-prices <file> | --price <fund>=<price>...) --reconcile <file> [options]
+       capgains --account <file> --solve-price <amount> --fund <fund> [--lot <date>...] [options]
+
+options:
+  --account <file>        csv file with the following fields -- Date,Fund,Transaction type,Shares transacted,Share price,Amount
+                          and optionally Fee, which is added to the cost basis of acquisitions and taken from the
+                          proceeds of disposals.
+                          May be given more than once to sell from several accounts. Several files separated by
+                          commas, e.g. one per year, are read as a single account.
+                          Short positions, from buying negative shares or selling more shares than are held,
+                          are not supported and are an error.
+  --tax-free-account <file>
+                          Like --account, for a tax-advantaged account such as an IRA whose capital gains are not
+                          taxed. Records of tax-free accounts are sold first. Only mincg supports several accounts.
+  --fund-prices <file>    csv file with the following fields -- Fund,Share price
+                          and optionally Date, the date the fund is sold at the price instead of --as-of, which
+                          ends the holding period of its records.
+                          Either file may be given as '-' to read it from stdin.
+  --price-date <date>     Read --fund-prices as a price history with the fields Fund,Date,Share price and use the
+                          price of each fund on the date, as YYYY-MM-DD, or on the latest earlier date, e.g. to
+                          reproduce a past sale with --as-of. Selling a fund without such a price fails.
+  --price-time <time>     Price read of a fund with several prices a day in --fund-prices, at different times in
+                          an optional Time column, as HH:MM or HH:MM:SS -- open (the earliest), close (the
+                          latest) or a time, for the latest price at or before it. A price without a time is
+                          only read for a fund without prices with a time. [default: close]
+  --price <fund>=<price>  Share price of a fund, e.g. VTSAX=110.25. May be given more than once. Overrides the
+                          price in --fund-prices.
+  --fund-aliases <file>   csv file with the fields -- Alias,Fund -- where Alias is another name of Fund, e.g. the
+                          name used in the account files for the ticker used in --fund-prices. Records of an
+                          alias are read as records of its fund.
+  --fund-alias <alias>=<fund>
+                          Like --fund-aliases, for a single alias. May be given more than once.
+  --fund-group <fund>=<group>
+                          Group share classes of the same underlying fund, e.g. --fund-group VTSAX=total-stock
+                          --fund-group VTSMX=total-stock. Each class keeps its own price, but --only-funds or
+                          --exclude-funds with the group applies to every class, and the totals are also shown
+                          for the group. May be given more than once.
+  --splits <file>         csv file of stock splits with the fields -- Fund,Date,Ratio -- where Ratio is e.g. 2:1.
+                          Records of a fund dated before one of its splits are adjusted to the shares and share
+                          prices after the split.
+  --col-date <name>       Name of the column read as Date. Likewise --col-fund, --col-transaction-type,
+                          --col-shares (Shares transacted), --col-share-price, --col-amount and --col-fee.
+  --date-format <format>  strftime format of the dates in the account file, e.g. %d.%m.%Y. By default, several
+                          common formats are accepted.
+  --currency <code>       Currency of the amounts and prices in the input files -- USD (default), EUR, GBP. Sets
+                          the currency symbol and the thousands and decimal separators, e.g. 1.234,56 € for EUR,
+                          and the symbol printed with the totals.
+  --input-format <format> Format of the input files -- csv or json, an array of objects with the same fields as
+                          the csv files. By default, files ending in .json are read as json and others as csv.
+  --delimiter <char>      Delimiter of the fields of the csv input files, e.g. ';', or tab for tab-separated files.
+                          [default: ,]
+  --basis-from-amount     Take the cost basis of each record from its Amount divided by its shares instead of from
+                          its Share price, when the Amount includes rounding or costs the price leaves out. A Fee
+                          is still added to the basis.
+  --adjust-wash-sales     Apply the wash-sale rule to the sells in the account files: the loss of a sell whose
+                          fund is acquired within 30 days before or after it is added to the basis of the
+                          replacement shares, which carry over the holding period of the shares sold.
+  --gzip                  Decompress the input files, e.g. when read from stdin. Files ending in .gz are always
+                          decompressed.
+  --target <amount>       Target amount to sell.
+  --target-percent <percent>
+                          Target amount to sell as a percentage of the value of the portfolio, e.g. 20, instead of
+                          --target. The value is after taxes unless --target-kind is gross, and only the records
+                          that can be sold count, see --only-funds and --sale-fee.
+  --target-kind <kind>    Whether --target is for the proceeds after taxes, net (default), or before, gross.
+  --target-shares <shares>
+                          Target number of shares of the fund given by --fund to sell, instead of an amount.
+  --target-cap-gains <amount>
+                          Target capital gains to realize, e.g. to offset a realized loss, instead of an amount.
+                          Records with the most gains per dollar are sold first. A negative target realizes losses.
+  --report                Report the unrealized capital gains of the whole account instead of selecting records
+                          to sell. Only table output is supported.
+  --list-funds            List the shares held, average cost, price and unrealized capital gains of each fund, with
+                          the most gains first, instead of selecting records to sell. Only table output is supported.
+  --solve-price <amount>  Find the lowest share price, in whole cents, of the fund given by --fund at which selling
+                          its records reaches the amount, after taxes unless --target-kind is gross, e.g. for the
+                          limit price of a sell order, instead of selecting records to sell.
+  --lot <date>            Purchase date of a record of --fund sold with --solve-price, as YYYY-MM-DD. May be given
+                          more than once. [default: all records of the fund]
+  --reconcile <file>      csv file with the fields -- Fund and either or both of Shares,Value -- the shares held
+                          of a fund or their value as stated by a broker. Checks that the records add up to each
+                          balance instead of selecting records to sell, e.g. to catch missing transactions, and
+                          fails if any balance is off by more than --reconcile-tolerance.
+  --reconcile-tolerance <amount>
+                          Largest difference in shares or value allowed by --reconcile. [default: 0.01]
+  --explain <fund>:<date> Explain how the amount, capital gains and taxes of selling the record of the fund
+                          purchased on the date are computed, e.g. VTSAX:2017-01-15.
+  --tax-rate <rate>       A flat tax rate to apply to capital gains, or short- and long-term rates separated by a comma
+                          (e.g. 0.35,0.15). Taxes will be accounted for when selecting records to sell. [default: 0]
+  --long-term-rate <rate> Tax rate to apply to long-term capital gains. Overrides the long-term rate of --tax-rate.
+  --fund-tax-rate <fund>=<rate>
+                          Tax rate to apply to all capital gains of a fund instead of the short- and long-term
+                          rates. May be given more than once.
+  --brackets <file>       csv file of progressive tax brackets with the fields -- Threshold,Rate -- where Rate is the
+                          marginal rate of income above Threshold. Applied to capital gains instead of the short-
+                          and long-term rates of --tax-rate. A single bracket at 0 is a flat rate.
+  --income <amount>       Ordinary income the capital gains stack on when applying --brackets. [default: 0]
+  --carryover-loss <amount>
+                          Capital losses carried over from earlier years, which offset the capital gains of the
+                          sale before taxes. [default: 0]
+  --as-of <date>          Date of the sale, as YYYY-MM-DD, used to value holding periods and classify capital gains
+                          as short or long term. [default: today]
+  --long-term-days <days> Records held for more than this many days are long term, for the holding period of
+                          another jurisdiction than the US. [default: 365]
+  --method <method>       Strategy used to select records to sell -- mincg (default), mintax (lowest taxes per
+                          dollar first, for records taxed at different rates), fifo, lifo, hifo, avgcost
+                          (average-cost basis, as elected for many mutual funds)
+  --format <format>       Output format of the sell summary -- table (default), json, csv, lots
+                          lots prints a line of fund,date purchased,shares per record sold, ready for a specific-lot
+                          sell order, followed by the account when selling from several accounts.
+  --output <file>         Write the sell summary, in any --format, to the file instead of stdout, which is left for
+                          the progress messages. The file is overwritten if it exists, and is not written if
+                          selling fails.
+  --only-funds <funds>    Comma-separated funds to sell from. Records of other funds are not sold.
+  --exclude-funds <funds> Comma-separated funds not to sell from.
+  --fractional            Sell the last record in thousandths of a share instead of whole shares, for funds that
+                          allow fractional shares.
+  --split-years <gains>   Spread the sale over two tax years: sell on --as-of until the target is reached or the
+                          capital gains would exceed the amount, e.g. the room left in a low tax bracket, and sell
+                          the rest on --next-as-of. Only with --target, mincg and table output.
+  --next-as-of <date>     Date of the sale of the next year with --split-years, as YYYY-MM-DD.
+                          [default: January 1 of the year after --as-of]
+  --whole-lots            Never sell part of a record. The last record is sold whole or not at all, whichever is
+                          closer to the target, so less than the target may be sold.
+  --min-shares <shares>   Smallest number of shares to sell of a record. Records with fewer shares are not sold and
+                          a partially sold record sells at least this many shares. Selling fails if the records
+                          that can be sold don't reach the target. [default: 0]
+  --max-lots <lots>       Largest number of records to sell with --target. Records are passed over when the target
+                          could no longer be reached with the rest, which can realize more capital gains.
+  --no-sell-after <date>  Latest purchase date of the records to sell with --target, e.g. to avoid short-term gains.
+                          Records purchased later are not sold.
+  --sale-fee <amount>     Fee paid for selling each record, e.g. a commission per trade. It's charged once per lot
+                          sold, not per share or per sale, and taken from the amount of the lot, which lowers its
+                          capital gains. Lots worth no more than the fee are not sold. [default: 0]
+  --round-to <places>     Number of decimal places of dollar amounts in table and csv output. [default: 2]
+  --group-thousands       Print dollar amounts in table output with the currency symbol and thousands separators,
+                          e.g. $123,456.78, instead of plain numbers.
+  --sort-by <key>[:<order>]
+                          Column to sort the records sold by -- date, fund, amount, cap-gains, cg-ratio -- and
+                          the order, asc (default) or desc. [default: date:desc]
+  -v, --verbose           Print the records in the order mincg considers them, with their capital gains ratio,
+                          before the records sold. Only shown with table output.
+  --interactive           Ask whether to sell each record of the recommendation, showing the proceeds of the
+                          records confirmed so far. Only with --target and table output, and not with input
+                          read from stdin.
+  --show-rows             Print the row of the account file each record sold was read from, counting from 1 after
+                          the header row, to find the record in the file. Only shown with table output.
+  --show-annualized       Print the annualized return of each record sold, compounded over its holding period, to
+                          compare the growth of the records, e.g. to keep those growing the most. Records sold on
+                          the day they were purchased have none. Only shown with table output.
+  --show-remaining        Print the shares and unrealized capital gains of each fund left after the sale. Only
+                          shown with table output.
+  --by-fund               Print the shares, amount and capital gains sold of each fund instead of each record sold.
+                          Only table output is affected.
+  --log-level <level>     Write diagnostics of the level and more important levels to stderr -- error, warn, info,
+                          debug (the records read and each step of the selection) or trace (every row read). If
+                          not given, the level is read from RUST_LOG, e.g. RUST_LOG=capgains=debug. [default: off]
+  -q, --quiet             Leave out notes on the sale, such as that it realizes a net capital loss, and the status
+                          line printed to stderr after selling, e.g.
+                            status=ok amount=500.00 cap_gains=50.00 tax=10.00 net=490.00
+                          or status=error code=<exit status> on an error.
+  --precision-check       Warn if the totals of the sale, which are summed in whole cents, differ by more than a cent
+                          from summing the records as floating-point numbers, as for very large accounts.
+  --merge-lots            Merge records of the same fund purchased on the same date at the same price.
+  --validate              Check that the amount of each record matches share price * shares.
+  --validate-tolerance <amount>
+                          Largest difference allowed by --validate. [default: 0.01]
+  --config <file>         Config file with default values for tax_rate, long_term_rate, output_format and
+                          date_format, one `key = value` per line. Flags override the config file.
+                          [default: capgains.toml, if it exists]
+  -h, --help              Print this message.
+
+exit status:
+  0  The records to sell, or the report, were printed.
+  1  Any other error, e.g. an invalid tax rate, a target that is not positive or a failed --reconcile.
+  2  Invalid command line arguments.
+  3  An input file could not be read or parsed, or the output could not be written.
+  4  Insufficient funds, shares or capital gains to reach the target.
+  5  A fund of the account has no price.
+";
+
+/// Exit status of an error not in any of the other categories.
+const EXIT_ERROR: i32 = 1;
+/// Exit status of invalid command line arguments.
+const EXIT_USAGE: i32 = 2;
+/// Exit status of an error reading an input file or writing the output.
+const EXIT_IO: i32 = 3;
+/// Exit status of a target that can't be reached.
+const EXIT_INSUFFICIENT: i32 = 4;
+/// Exit status of a fund without a price.
+const EXIT_MISSING_PRICE: i32 = 5;
+
+/// What the tool has been asked to do.
+#[derive(Clone, Debug)]
+enum Mode {
+    /// Select records to sell for a target amount, or a percentage of the value of the portfolio
+    /// with `Args::target_percent`.
+    Sell(f64),
+    /// Select records to sell for a target number of shares of a fund.
+    SellShares(String, f64),
+    /// Select records to sell for a target amount of capital gains.
+    SellCapGains(f64),
+    /// Report the unrealized capital gains of all records.
+    Report,
+    /// List the shares, average cost, price and unrealized capital gains of each fund.
+    ListFunds,
+    /// Check the records against the stated balances of a file.
+    Reconcile(String),
+    /// Find the share price of a fund at which selling its records purchased on the dates, or all
+    /// of its records, reaches a target amount.
+    SolvePrice(String, f64, Vec<chrono::NaiveDate>),
+    /// Explain the sale of the records of a fund purchased on a date.
+    Explain(String, chrono::NaiveDate),
 }
 
-fn de_usd_from_str<'de, D>(deserializer: D) -> Result<f64, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s: &str = Deserialize::deserialize(deserializer)?;
-    let clean_s = str::replace(s.trim_matches('$'), ",", "");
-    f64::from_str(&clean_s).map_err(de::Error::custom)
+/// Parsed command line arguments.
+#[derive(Debug)]
+struct Args {
+    /// Account files and whether their capital gains are taxed.
+    accounts: Vec<(String, bool)>,
+    fund_prices: Option<String>,
+    /// Date of the prices read from a fund prices file of price history.
+    price_date: Option<chrono::NaiveDate>,
+    /// File of stock splits applied to the records of every account.
+    splits: Option<String>,
+    /// File of fund aliases applied to the records of every account.
+    fund_aliases: Option<String>,
+    /// Fund aliases given on the command line, which override the fund aliases file.
+    aliases: Vec<(String, String)>,
+    /// Group of share classes of each fund given with --fund-group.
+    fund_groups: HashMap<String, String>,
+    /// Fund prices given on the command line, which override the fund prices file.
+    prices: Vec<(String, f64)>,
+    mode: Mode,
+    /// Whether the target of `Mode::Sell` is a percentage of the value of the portfolio, which is
+    /// only known once the accounts are read.
+    target_percent: bool,
+    tax_rates: TaxRates,
+    /// File of tax brackets applied instead of the short- and long-term rates.
+    brackets: Option<String>,
+    /// Ordinary income the capital gains stack on when applying tax brackets.
+    income: f64,
+    /// Date the records are sold on, up to which holding periods are counted.
+    sale_date: chrono::NaiveDate,
+    method: Method,
+    format: Format,
+    merge_lots: bool,
+    /// Move the disallowed losses of wash sales in the account files to the replacement shares.
+    adjust_wash_sales: bool,
+    /// Print the ranking of the records before the records sold.
+    verbose: bool,
+    show_remaining: bool,
+    /// Warn when summing the records sold as floating-point numbers changes the totals.
+    precision_check: bool,
+    interactive: bool,
+    validate: bool,
+    validate_tolerance: f64,
+    /// Largest difference between a stated balance and the records that passes --reconcile.
+    reconcile_tolerance: f64,
+    /// Level of the diagnostics written to stderr, from --log-level or else RUST_LOG.
+    log_level: Option<log::Level>,
+    /// Largest capital gains sold on the sale date with --split-years, the rest being sold on the
+    /// next sale date.
+    split_years: Option<f64>,
+    next_sale_date: Option<chrono::NaiveDate>,
+    /// File the sell summary is written to instead of stdout.
+    output: Option<String>,
+    input_options: InputOptions,
+    sell_options: SellOptions,
+    output_options: OutputOptions,
 }
 
-struct Account {
-    records: Vec<Record>,
-    funds: HashSet<String>,
+#[derive(Debug)]
+enum ArgsError {
+    Help,
+    Invalid(String),
 }
 
-impl Account {
-    fn new(records: Vec<Record>) -> Self {
-        let mut funds = HashSet::new();
-        for record in &records {
-            if !funds.contains(&record.fund) {
-                funds.insert(record.fund.clone());
+impl Args {
+    fn parse<I: Iterator<Item = String>>(argv: I) -> Result<Args, ArgsError> {
+        // split "--flag=value" into "--flag" and "value"
+        let mut tokens = Vec::new();
+        for arg in argv {
+            match arg.find('=') {
+                Some(i) if arg.starts_with("--") => {
+                    tokens.push(arg[..i].to_string());
+                    tokens.push(arg[i + 1..].to_string());
+                }
+                _ => tokens.push(arg),
             }
         }
 
-        Account { records, funds }
-    }
+        // the config file provides the defaults, so it is read before the other flags
+        let config_file = match tokens.iter().position(|t| t == "--config") {
+            Some(i) => match tokens.get(i + 1) {
+                Some(file) => Some(file.as_str()),
+                None => return Err(ArgsError::Invalid("a value is required for '--config'".to_string())),
+            },
+            None => None,
+        };
+        let config = Config::load(config_file).map_err(ArgsError::Invalid)?;
 
-    fn make_sell_records<'a>(
-        &self,
-        fund_prices: &'a HashMap<String, f64>,
-    ) -> Result<Vec<SellRecord<'a>>, AccountError> {
-        for fund in self.funds.iter() {
-            if !fund_prices.contains_key(fund) {
-                let s = format!("Missing price for fund: {}", fund);
-                return Err(AccountError(s));
+        let mut accounts = Vec::new();
+        let mut fund_prices = None;
+        let mut price_date = None;
+        let mut splits = None;
+        let mut fund_aliases = None;
+        let mut aliases = Vec::new();
+        let mut fund_groups = HashMap::new();
+        let mut prices: Vec<(String, f64)> = Vec::new();
+        let mut target = None;
+        let mut target_percent = None;
+        let mut target_shares = None;
+        let mut target_cap_gains = None;
+        let mut fund = None;
+        let mut tax_rates = config.tax_rate.unwrap_or_else(|| TaxRates::flat(0.0));
+        let mut fund_tax_rates = Vec::new();
+        let mut brackets = None;
+        let mut income = 0.0;
+        let mut carryover_loss: f64 = 0.0;
+        let mut sale_date = chrono::Local::now().naive_local().date();
+        let mut long_term_rate = config.long_term_rate;
+        let mut method = Method::MinimumCapGains;
+        let mut format = config.output_format.unwrap_or(Format::Table);
+        let mut merge_lots = false;
+        let mut adjust_wash_sales = false;
+        let mut verbose = false;
+        let mut show_remaining = false;
+        let mut precision_check = false;
+        let mut interactive = false;
+        let mut report = false;
+        let mut list_funds = false;
+        let mut reconcile = None;
+        let mut solve_price = None;
+        let mut split_years = None;
+        let mut next_sale_date = None;
+        let mut output = None;
+        let mut lots = Vec::new();
+        let mut reconcile_tolerance = 0.01;
+        let mut log_level = env::var("RUST_LOG").ok().and_then(|value| log::level_from_env(&value));
+        let mut explain = None;
+        let mut validate = false;
+        let mut validate_tolerance = 0.01;
+        let mut input_options = InputOptions {
+            date_format: config.date_format,
+            ..InputOptions::default()
+        };
+        let mut sell_options = SellOptions::default();
+        let mut fund_filter = None;
+        let mut output_options = OutputOptions::default();
+
+        let mut tokens = tokens.into_iter();
+        while let Some(flag) = tokens.next() {
+            match flag.as_str() {
+                "-h" | "--help" => return Err(ArgsError::Help),
+                "--fractional" => {
+                    sell_options.fractional = true;
+                    continue;
+                }
+                "--whole-lots" => {
+                    sell_options.whole_lots = true;
+                    continue;
+                }
+                "--basis-from-amount" => {
+                    sell_options.basis_from_amount = true;
+                    continue;
+                }
+                "--merge-lots" => {
+                    merge_lots = true;
+                    continue;
+                }
+                "--adjust-wash-sales" => {
+                    adjust_wash_sales = true;
+                    continue;
+                }
+                "--validate" => {
+                    validate = true;
+                    continue;
+                }
+                "--gzip" => {
+                    input_options.gzip = true;
+                    continue;
+                }
+                "-v" | "--verbose" => {
+                    verbose = true;
+                    continue;
+                }
+                "--interactive" => {
+                    interactive = true;
+                    continue;
+                }
+                "--show-remaining" => {
+                    show_remaining = true;
+                    continue;
+                }
+                "--precision-check" => {
+                    precision_check = true;
+                    continue;
+                }
+                "--by-fund" => {
+                    output_options.by_fund = true;
+                    continue;
+                }
+                "--show-rows" => {
+                    output_options.show_rows = true;
+                    continue;
+                }
+                "--show-annualized" => {
+                    output_options.show_annualized = true;
+                    continue;
+                }
+                "--group-thousands" => {
+                    output_options.grouped = true;
+                    continue;
+                }
+                "-q" | "--quiet" => {
+                    output_options.quiet = true;
+                    continue;
+                }
+                "--report" => {
+                    report = true;
+                    continue;
+                }
+                "--list-funds" => {
+                    list_funds = true;
+                    continue;
+                }
+                _ => {}
             }
-        }
 
-        let mut vec = Vec::new();
-        for record in &self.records {
-            let date_purchased = record.date;
-            let fund = fund_prices.get_key_value(&record.fund).unwrap().0;
-            let num_shares = record.num_shares;
-            let share_price_purchased = record.share_price;
-            let share_price = *fund_prices.get(&record.fund).unwrap();
-            let amount = share_price * num_shares;
-            let cap_gains = (share_price - share_price_purchased) * num_shares;
-            let cap_gains_ratio = cap_gains / amount;
+            if !flag.starts_with('-') {
+                return Err(ArgsError::Invalid(format!("unexpected argument '{}'", flag)));
+            }
+            let value = tokens
+                .next()
+                .ok_or_else(|| ArgsError::Invalid(format!("a value is required for '{}'", flag)))?;
 
-            vec.push(SellRecord {
-                date_purchased,
-                fund,
-                num_shares,
-                share_price_purchased,
-                share_price,
-                amount,
-                cap_gains,
-                cap_gains_ratio,
-            });
+            match flag.as_str() {
+                "--config" => {}
+                "--account" => accounts.push((value, true)),
+                "--tax-free-account" => accounts.push((value, false)),
+                "--fund-prices" => fund_prices = Some(value),
+                "--price-date" => price_date = Some(parse_value(&flag, &value)?),
+                "--reconcile" => reconcile = Some(value),
+                "--reconcile-tolerance" => reconcile_tolerance = parse_value(&flag, &value)?,
+                "--log-level" => log_level = Some(parse_value(&flag, &value)?),
+                "--splits" => splits = Some(value),
+                "--explain" => explain = Some(parse_lot(&flag, &value)?),
+                "--fund-aliases" => fund_aliases = Some(value),
+                "--fund-alias" => aliases.push(parse_assignment(&flag, &value)?),
+                "--fund-group" => {
+                    let (fund, group) = parse_assignment(&flag, &value)?;
+                    fund_groups.insert(fund, group);
+                }
+                "--price" => prices.push(parse_assignment(&flag, &value)?),
+                "--target" => target = Some(parse_value(&flag, &value)?),
+                "--target-percent" => target_percent = Some(parse_value(&flag, &value)?),
+                "--target-cap-gains" => target_cap_gains = Some(parse_value(&flag, &value)?),
+                "--target-shares" => target_shares = Some(parse_value(&flag, &value)?),
+                "--fund" => fund = Some(value),
+                "--solve-price" => solve_price = Some(parse_value(&flag, &value)?),
+                "--split-years" => split_years = Some(parse_value(&flag, &value)?),
+                "--next-as-of" => next_sale_date = Some(parse_value(&flag, &value)?),
+                "--lot" => lots.push(parse_value(&flag, &value)?),
+                "--tax-rate" => tax_rates = parse_value(&flag, &value)?,
+                "--fund-tax-rate" => fund_tax_rates.push(parse_assignment(&flag, &value)?),
+                "--brackets" => brackets = Some(value),
+                "--income" => income = parse_value(&flag, &value)?,
+                "--carryover-loss" => carryover_loss = parse_value(&flag, &value)?,
+                "--as-of" => sale_date = parse_value(&flag, &value)?,
+                "--long-term-rate" => long_term_rate = Some(parse_value(&flag, &value)?),
+                "--method" => method = parse_value(&flag, &value)?,
+                "--format" => format = parse_value(&flag, &value)?,
+                "--output" => output = Some(value),
+                "--validate-tolerance" => validate_tolerance = parse_value(&flag, &value)?,
+                "--target-kind" => {
+                    sell_options.target_kind = parse_value(&flag, &value)?;
+                    output_options.target_kind = sell_options.target_kind;
+                }
+                "--min-shares" => sell_options.min_shares = parse_value(&flag, &value)?,
+                "--max-lots" => sell_options.max_lots = Some(parse_value(&flag, &value)?),
+                "--no-sell-after" => sell_options.no_sell_after = Some(parse_value(&flag, &value)?),
+                "--long-term-days" => sell_options.long_term_days = Some(parse_value(&flag, &value)?),
+                "--sale-fee" => sell_options.sale_fee = parse_value(&flag, &value)?,
+                "--round-to" => output_options.precision = parse_value(&flag, &value)?,
+                "--sort-by" => {
+                    let (key, order) = match value.find(':') {
+                        Some(i) => (&value[..i], &value[i + 1..]),
+                        None => (value.as_str(), "asc"),
+                    };
+                    output_options.sort_by = parse_value::<SortKey>(&flag, key)?;
+                    output_options.descending = match order {
+                        "asc" => false,
+                        "desc" => true,
+                        _ => {
+                            return Err(ArgsError::Invalid(format!(
+                                "invalid value '{}' for '{}': expected an order of asc or desc",
+                                value, flag
+                            )))
+                        }
+                    };
+                }
+                "--col-date" => input_options.rename_column(&value, "Date"),
+                "--col-fund" => input_options.rename_column(&value, "Fund"),
+                "--col-transaction-type" => input_options.rename_column(&value, "Transaction type"),
+                "--col-shares" => input_options.rename_column(&value, "Shares transacted"),
+                "--col-share-price" => input_options.rename_column(&value, "Share price"),
+                "--col-amount" => input_options.rename_column(&value, "Amount"),
+                "--col-fee" => input_options.rename_column(&value, "Fee"),
+                "--date-format" => input_options.date_format = Some(value),
+                "--input-format" => input_options.format = Some(parse_value(&flag, &value)?),
+                "--delimiter" => input_options.delimiter = Some(parse_delimiter(&value)?),
+                "--price-time" => input_options.price_time = parse_value(&flag, &value)?,
+                "--currency" => {
+                    let currency: Currency = parse_value(&flag, &value)?;
+                    input_options.currency = currency.clone();
+                    output_options.currency = currency;
+                }
+                "--only-funds" | "--exclude-funds" => {
+                    if fund_filter.is_some() {
+                        return Err(ArgsError::Invalid(
+                            "only one of '--only-funds' and '--exclude-funds' can be given, once".to_string(),
+                        ));
+                    }
+                    let funds = value.split(',').map(|f| f.trim().to_string()).filter(|f| !f.is_empty());
+                    fund_filter = Some(if flag == "--only-funds" {
+                        FundFilter::Only(funds.collect())
+                    } else {
+                        FundFilter::Exclude(funds.collect())
+                    });
+                }
+                _ => return Err(ArgsError::Invalid(format!("unexpected argument '{}'", flag))),
+            }
         }
 
-        Ok(vec)
-    }
+        if let Some(long_term) = long_term_rate {
+            tax_rates.long_term = long_term;
+        }
+        tax_rates.per_fund.extend(fund_tax_rates);
+        if carryover_loss.is_nan() || carryover_loss < 0.0 {
+            let msg = format!(
+                "invalid value '{}' for '--carryover-loss': must not be negative",
+                carryover_loss
+            );
+            return Err(ArgsError::Invalid(msg));
+        }
+        tax_rates.carryover_loss = carryover_loss;
+        if let Some(days) = sell_options.long_term_days.filter(|&days| days < 0) {
+            let msg = format!("invalid value '{}' for '--long-term-days': must not be negative", days);
+            return Err(ArgsError::Invalid(msg));
+        }
+        if !(sell_options.sale_fee >= 0.0 && sell_options.sale_fee.is_finite()) {
+            let msg = format!("invalid value '{}' for '--sale-fee': must not be negative", sell_options.sale_fee);
+            return Err(ArgsError::Invalid(msg));
+        }
+        tax_rates.validate().map_err(|err| ArgsError::Invalid(err.to_string()))?;
+        let targets: [(&str, Option<f64>); 3] =
+            [("--target", target), ("--target-shares", target_shares), ("--solve-price", solve_price)];
+        for &(flag, value) in &targets {
+            match value {
+                Some(value) if value.is_nan() || value <= 0.0 => {
+                    let msg = format!("invalid value '{}' for '{}': must be positive", value, flag);
+                    return Err(ArgsError::Invalid(msg));
+                }
+                _ => {}
+            }
+        }
+        for &(ref fund, price) in &prices {
+            if price.is_nan() || price <= 0.0 {
+                let msg = format!("invalid value '{}={}' for '--price': must be positive", fund, price);
+                return Err(ArgsError::Invalid(msg));
+            }
+        }
+        if let Some(funds) = fund_filter {
+            sell_options.funds = funds;
+        }
 
-    fn minimum_cap_gains<'a>(
-        &self,
-        fund_prices: &'a HashMap<String, f64>,
-        sell_target: f64,
-        tax_rate: f64,
-    ) -> Result<Vec<SellRecord<'a>>, AccountError> {
-        let mut sell_records = self.make_sell_records(fund_prices)?;
-
-        let mut indices = Vec::new();
-        for (i, item) in sell_records.iter().enumerate() {
-            indices.push((item.cap_gains_ratio, i));
-        }
-        indices.sort_unstable_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
-
-        let mut sell_records: Vec<Option<SellRecord>> =
-            sell_records.drain(..)
-                        .map(|s| Some(s))
-                        .collect();
-
-        let mut amount = 0.0;
-        let mut cap_gains = 0.0;
-        let mut result = Vec::new();
-        for (_, i) in indices {
-            sell_records.push(None);
-            let srec = sell_records.swap_remove(i).unwrap();
-            amount += srec.amount;
-            cap_gains += srec.cap_gains;
-
-            // TODO: handle negative cap gains
-            if (amount - cap_gains * tax_rate) > sell_target {
-                // see if we can sell some (not all) of the shares of this record
-                let mut x = srec.amount - srec.cap_gains * tax_rate;
-                x /= srec.num_shares;
-
-                // get pre-record values for amount and cap gains
-                let a = amount - srec.amount;
-                let c = cap_gains - srec.cap_gains;
-
-                // get number of shares needed to reach sell target
-                // shares can only be sold as integer amounts
-                let n = ((sell_target - (a - c * tax_rate)) / x).trunc() + 1.0;
-
-                if n < srec.num_shares.trunc() {
-                    result.push(
-                        SellRecord {
-                            num_shares: n,
-                            amount: srec.share_price*n,
-                            cap_gains: (srec.share_price - srec.share_price_purchased)*n,
-                            ..srec
-                        }
-                    );
-                } else {
-                    result.push(srec);
+        if accounts.is_empty() {
+            return Err(ArgsError::Invalid(
+                "the following required argument was not provided: --account".to_string(),
+            ));
+        }
+        if accounts.len() > 1 {
+            match method {
+                Method::MinimumCapGains => {}
+                _ => {
+                    return Err(ArgsError::Invalid(
+                        "only '--method mincg' can be used with more than one account".to_string(),
+                    ))
                 }
-                break;
-            } else {
-                result.push(srec);
+            }
+            if target_shares.is_some() {
+                return Err(ArgsError::Invalid(
+                    "'--target-shares' cannot be used with more than one account".to_string(),
+                ));
+            }
+            if target_cap_gains.is_some() {
+                return Err(ArgsError::Invalid(
+                    "'--target-cap-gains' cannot be used with more than one account".to_string(),
+                ));
+            }
+            if solve_price.is_some() {
+                return Err(ArgsError::Invalid(
+                    "'--solve-price' cannot be used with more than one account".to_string(),
+                ));
             }
         }
+        // the price of the fund sold is what --solve-price finds
+        if fund_prices.is_none() && prices.is_empty() && solve_price.is_none() {
+            return Err(ArgsError::Invalid(
+                "the following required argument was not provided: --fund-prices or --price".to_string(),
+            ));
+        }
+        let account_files = accounts.iter().flat_map(|a| a.0.split(','));
+        let other_files = fund_prices.iter().chain(splits.iter()).chain(fund_aliases.iter()).chain(reconcile.iter());
+        let inputs = account_files.chain(other_files.map(|f| f.as_str()));
+        let stdin_inputs = inputs.filter(|&f| f == STDIN).count();
+        if stdin_inputs > 1 {
+            let files = "the accounts, '--fund-prices', '--splits', '--fund-aliases' and '--reconcile'";
+            return Err(ArgsError::Invalid(format!("only one of {} can be read from stdin", files)));
+        }
 
-        if amount < sell_target {
-            return Err(AccountError("Insufficient funds.".to_string()));
+        if price_date.is_some() && fund_prices.is_none() {
+            return Err(ArgsError::Invalid("'--price-date' can only be used with '--fund-prices'".to_string()));
+        }
+        if verbose && method != Method::MinimumCapGains {
+            return Err(ArgsError::Invalid("'--verbose' can only be used with '--method mincg'".to_string()));
+        }
+        if fund.is_some() && target_shares.is_none() && solve_price.is_none() {
+            let msg = "'--fund' can only be used with '--target-shares' or '--solve-price'";
+            return Err(ArgsError::Invalid(msg.to_string()));
+        }
+        if !lots.is_empty() && solve_price.is_none() {
+            return Err(ArgsError::Invalid("'--lot' can only be used with '--solve-price'".to_string()));
+        }
+        if let Some(percent) = target_percent.filter(|&percent: &f64| !(percent > 0.0 && percent <= 100.0)) {
+            let msg = format!("invalid value '{}' for '--target-percent': must be above 0 and at most 100", percent);
+            return Err(ArgsError::Invalid(msg));
+        }
+        if target.is_some() && target_percent.is_some() {
+            return Err(ArgsError::Invalid("'--target-percent' cannot be used with '--target'".to_string()));
+        }
+        let target = target.or(target_percent);
+        let views = (report, list_funds, reconcile, solve_price);
+        let mode = match (views, explain, target, target_shares, target_cap_gains) {
+            ((true, false, None, None), None, None, None, None) => Mode::Report,
+            ((false, true, None, None), None, None, None, None) => Mode::ListFunds,
+            ((false, false, Some(file), None), None, None, None, None) => Mode::Reconcile(file),
+            ((false, false, None, Some(target)), None, None, None, None) => {
+                Mode::SolvePrice(required(fund, "--fund")?, target, lots)
+            }
+            ((false, false, None, None), Some((fund, date)), None, None, None) => Mode::Explain(fund, date),
+            ((false, false, None, None), None, Some(target), None, None) => Mode::Sell(target),
+            ((false, false, None, None), None, None, Some(shares), None) => {
+                match method {
+                    Method::MinimumCapGains => {}
+                    _ => {
+                        return Err(ArgsError::Invalid(
+                            "only '--method mincg' can be used with '--target-shares'".to_string(),
+                        ))
+                    }
+                }
+                Mode::SellShares(required(fund, "--fund")?, shares)
+            }
+            ((false, false, None, None), None, None, None, Some(cap_gains)) => {
+                if method != Method::MinimumCapGains {
+                    return Err(ArgsError::Invalid(
+                        "'--method' cannot be used with '--target-cap-gains'".to_string(),
+                    ));
+                }
+                Mode::SellCapGains(cap_gains)
+            }
+            ((false, false, None, None), None, None, None, None) => Mode::Sell(required(target, "--target")?),
+            _ => {
+                let targets = "'--target', '--target-shares', '--target-cap-gains', '--report', '--list-funds', \
+                               '--reconcile', '--solve-price' and '--explain'";
+                return Err(ArgsError::Invalid(format!("only one of {} can be used", targets)));
+            }
+        };
+        match (sell_options.max_lots, &mode) {
+            (Some(0), _) => {
+                let msg = "invalid value '0' for '--max-lots': must be positive";
+                return Err(ArgsError::Invalid(msg.to_string()));
+            }
+            (Some(_), &Mode::Sell(_)) | (None, _) => {}
+            (Some(_), _) => {
+                return Err(ArgsError::Invalid("'--max-lots' can only be used with '--target'".to_string()));
+            }
+        }
+        if let Some(limit) = split_years.filter(|&limit: &f64| limit.is_nan() || limit < 0.0) {
+            let msg = format!("invalid value '{}' for '--split-years': must not be negative", limit);
+            return Err(ArgsError::Invalid(msg));
+        }
+        let split_error = match mode {
+            _ if split_years.is_none() => None,
+            Mode::Sell(_) if accounts.len() > 1 => Some("cannot be used with more than one account"),
+            Mode::Sell(_) if method != Method::MinimumCapGains => Some("can only be used with '--method mincg'"),
+            Mode::Sell(_) if format != Format::Table => Some("can only be used with table output"),
+            Mode::Sell(_) if interactive => Some("cannot be used with '--interactive'"),
+            Mode::Sell(_) => None,
+            _ => Some("can only be used with '--target'"),
+        };
+        if let Some(msg) = split_error {
+            return Err(ArgsError::Invalid(format!("'--split-years' {}", msg)));
+        }
+        if next_sale_date.is_some() && split_years.is_none() {
+            return Err(ArgsError::Invalid("'--next-as-of' can only be used with '--split-years'".to_string()));
+        }
+        let output_error = match mode {
+            _ if output.is_none() => None,
+            Mode::Sell(_) if split_years.is_some() => Some("cannot be used with '--split-years'"),
+            Mode::Sell(_) | Mode::SellShares(..) | Mode::SellCapGains(_) => None,
+            _ => Some("can only be used with '--target', '--target-shares' or '--target-cap-gains'"),
+        };
+        if let Some(msg) = output_error {
+            return Err(ArgsError::Invalid(format!("'--output' {}", msg)));
+        }
+        let interactive_error = match mode {
+            _ if !interactive => None,
+            Mode::Sell(_) if format != Format::Table => Some("can only be used with table output"),
+            Mode::Sell(_) if stdin_inputs > 0 => Some("cannot be used with input read from stdin"),
+            Mode::Sell(_) => None,
+            _ => Some("can only be used with '--target'"),
+        };
+        if let Some(msg) = interactive_error {
+            return Err(ArgsError::Invalid(format!("'--interactive' {}", msg)));
         }
 
-        Ok(result)
+        Ok(Args {
+            accounts,
+            fund_prices,
+            price_date,
+            splits,
+            fund_aliases,
+            aliases,
+            fund_groups,
+            prices,
+            mode,
+            target_percent: target_percent.is_some(),
+            tax_rates,
+            brackets,
+            income,
+            sale_date,
+            method,
+            format,
+            merge_lots,
+            adjust_wash_sales,
+            verbose,
+            show_remaining,
+            precision_check,
+            interactive,
+            validate,
+            validate_tolerance,
+            reconcile_tolerance,
+            log_level,
+            split_years,
+            next_sale_date,
+            output,
+            input_options,
+            sell_options,
+            output_options,
+        })
     }
 }
 
-fn load_account(filename: &str) -> Result<Account, csv::Error> {
-    let mut rdr = csv::Reader::from_path(filename)?;
-    let mut vec = Vec::new();
-    let mut error: Option<csv::Error> = None;
-
-    for result in rdr.deserialize::<Record>() {
-        match result {
-            Ok(record) => {
-                match error {
-                    Some(error) => return Err(error),
-                    None => vec.push(record),
-                };
-            }
-            Err(err) => error = Some(err),
-        };
-    }
+fn parse_value<T>(flag: &str, value: &str) -> Result<T, ArgsError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    T::from_str(value)
+        .map_err(|err| ArgsError::Invalid(format!("invalid value '{}' for '{}': {}", value, flag, err)))
+}
 
-    Ok(Account::new(vec))
+/// Parses the value of --delimiter, a single ASCII character, or "tab" or "\t" for a tab.
+fn parse_delimiter(value: &str) -> Result<u8, ArgsError> {
+    match value.as_bytes() {
+        b"tab" | b"\\t" => Ok(b'\t'),
+        &[delimiter] if delimiter.is_ascii() => Ok(delimiter),
+        _ => Err(ArgsError::Invalid(format!(
+            "invalid value '{}' for '--delimiter': expected a single character or 'tab'",
+            value
+        ))),
+    }
 }
 
-fn load_fund_prices(filename: &str) -> Result<HashMap<String, f64>, csv::Error> {
-    let mut fund_prices: HashMap<String, f64> = HashMap::new();
-    let mut rdr = csv::Reader::from_path(filename)?;
+/// Parses a value of the form `<name>=<value>`.
+fn parse_assignment<T>(flag: &str, value: &str) -> Result<(String, T), ArgsError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    match value.find('=') {
+        Some(i) if i > 0 => Ok((value[..i].to_string(), parse_value(flag, &value[i + 1..])?)),
+        _ => Err(ArgsError::Invalid(format!(
+            "invalid value '{}' for '{}': expected <name>=<value>",
+            value, flag
+        ))),
+    }
+}
 
-    for result in rdr.deserialize::<FundPrice>() {
-        match result {
-            Ok(fp) => fund_prices.insert(fp.fund, fp.share_price),
-            Err(err) => return Err(err),
-        };
+/// Parses a value of the form `<fund>:<date>`.
+fn parse_lot(flag: &str, value: &str) -> Result<(String, chrono::NaiveDate), ArgsError> {
+    match value.rfind(':') {
+        Some(i) if i > 0 => Ok((value[..i].to_string(), parse_value(flag, &value[i + 1..])?)),
+        _ => Err(ArgsError::Invalid(format!(
+            "invalid value '{}' for '{}': expected <fund>:<date>",
+            value, flag
+        ))),
     }
+}
 
-    Ok(fund_prices)
+fn required<T>(value: Option<T>, flag: &str) -> Result<T, ArgsError> {
+    value.ok_or_else(|| ArgsError::Invalid(format!("the following required argument was not provided: {}", flag)))
 }
 
-fn print_sell_summary(mut summary: Vec<SellRecord>, tax_rate: f64) {
-    summary.sort_unstable_by(|a, b| b.date_purchased.cmp(&a.date_purchased));
-    println!("Selling the following records:");
-
-    let mut amount = 0.0;
-    let mut cap_gains = 0.0;
-    println!("  {:>10}, {:>25}, {:>10}, {:>10}, {:>10}", "date", "fund", "amount", "cap gains", "shares");
-    for srec in summary {
-        // print out when selling a whole number of shares as it's not too common
-        let shares = if srec.num_shares.fract() == 0.0 {
-            format!("{:>10} [whole]", srec.num_shares)
-        } else {
-            format!("{:10.3}", srec.num_shares)
-        };
-        println!(
-            "  {}, {:>25}, {:10.3}, {:10.3}, {}",
-            srec.date_purchased, srec.fund, srec.amount, srec.cap_gains, shares
-        );
-        amount += srec.amount;
-        cap_gains += srec.cap_gains;
+fn run(args: &Args) -> Result<(), Box<dyn error::Error>> {
+    let splits = match args.splits {
+        Some(ref filename) => load_splits(filename, &args.input_options)?,
+        None => Vec::new(),
+    };
+    let mut aliases = match args.fund_aliases {
+        Some(ref filename) => load_fund_aliases(filename, &args.input_options)?,
+        None => HashMap::new(),
+    };
+    aliases.extend(args.aliases.iter().cloned());
+    let mut accounts = Vec::new();
+    for &(ref filename, taxable) in &args.accounts {
+        let filenames: Vec<&str> = filename.split(',').collect();
+        let mut account = load_accounts(&filenames, &args.input_options)?
+            .with_taxable(taxable)
+            .with_wash_sale_adjustment(args.adjust_wash_sales)
+            .with_fund_aliases(&aliases)
+            .with_fund_groups(&args.fund_groups)
+            .with_splits(&splits);
+        if args.validate {
+            account.validate_amounts(args.validate_tolerance)?;
+        }
+        // the records sold only need to be told apart when selling from several accounts
+        if args.accounts.len() > 1 {
+            account = account.with_name(filename);
+        }
+        accounts.push(account);
     }
-
-    println!("will result in");
-    println!("amount:     {:10.3}", amount);
-    println!("cap gains:  {:10.3}", cap_gains);
-    if tax_rate != 0.0 {
-        println!("taxes:      {:10.3}", cap_gains * tax_rate);
-        println!("net amount: {:10.3}", amount - cap_gains * tax_rate);
+    let portfolio = Portfolio::new(accounts);
+    let (mut fund_prices, mut sale_dates) = match (&args.fund_prices, args.price_date) {
+        // the dates of a price history are of the prices, not of the sale
+        (Some(filename), Some(date)) => (load_fund_prices_on(filename, date, &args.input_options)?, HashMap::new()),
+        (Some(filename), None) => load_fund_prices_and_dates(filename, &args.input_options)?,
+        (None, _) => (HashMap::new(), HashMap::new()),
+    };
+    // a price given on the command line is for the sale date
+    for (fund, _) in &args.prices {
+        sale_dates.remove(fund);
+    }
+    fund_prices.extend(args.prices.iter().cloned());
+    let sale_date = args.sale_date;
+    let mut tax_rates = args.tax_rates.clone();
+    if let Some(ref filename) = args.brackets {
+        tax_rates.brackets = Some(load_tax_brackets(filename, args.income, &args.input_options)?);
+        tax_rates.validate()?;
     }
+    let tax_rates = &tax_rates;
+    let options = &SellOptions {
+        sale_dates,
+        ..args.sell_options.clone()
+    };
+    let mode = match args.mode {
+        Mode::Sell(percent) if args.target_percent => {
+            // the value is of the same kind as the target, after taxes for a net target, and only
+            // of the records that can be sold, e.g. not of those worth less than the sale fee
+            let mut sell_records = portfolio.make_sell_records(&fund_prices, tax_rates, sale_date, options)?;
+            sell_records.retain(|srec| options.can_sell(srec));
+            let summary = SellSummary::new(sell_records, tax_rates);
+            let value = options.target_kind.proceeds(summary.total_amount, summary.total_tax);
+            if value <= 0.0 {
+                let msg = format!(
+                    "'--target-percent' can't be used as the value of the portfolio is {:.2}: no records can be sold",
+                    value.max(0.0)
+                );
+                return Err(msg.into());
+            }
+            Mode::Sell(value * percent / 100.0)
+        }
+        ref mode => mode.clone(),
+    };
+    let ranked = matches!(mode, Mode::Sell(_) | Mode::SellShares(..));
+    if args.verbose && args.format == Format::Table && ranked {
+        let mut candidates = portfolio.ranked_sell_records(&fund_prices, tax_rates, sale_date, options)?;
+        if let Mode::SellShares(ref fund, _) = mode {
+            candidates.retain(|s| s.fund == fund);
+        }
+        print_candidates(&candidates, &args.output_options);
+    }
+    let (mut result, sell_target) = match (&mode, portfolio.accounts()) {
+        (&Mode::Report, _) => {
+            let sell_records = portfolio.make_sell_records(&fund_prices, tax_rates, sale_date, options)?;
+            print_report(&sell_records, tax_rates, &args.output_options);
+            return Ok(());
+        }
+        (&Mode::ListFunds, _) => {
+            let sell_records = portfolio.make_sell_records(&fund_prices, tax_rates, sale_date, options)?;
+            print_fund_list(&sell_records, &args.output_options);
+            return Ok(());
+        }
+        (&Mode::SolvePrice(ref fund, target, ref dates), [account]) => {
+            let share_price = account.solve_price(fund, dates, target, tax_rates, sale_date, options)?;
+            let share_price = args.output_options.currency.format(share_price, 2);
+            println!("Selling requires a share price of {} of at least: {}", fund, share_price);
+            return Ok(());
+        }
+        (Mode::Reconcile(filename), _) => {
+            let balances = load_balances(filename, &args.input_options)?;
+            let sell_records = portfolio.make_sell_records(&fund_prices, tax_rates, sale_date, options)?;
+            let checks = reconcile(&sell_records, &balances);
+            print_reconciliation(&checks, args.reconcile_tolerance, &args.output_options);
+            let count = checks.iter().filter(|check| !check.matches(args.reconcile_tolerance)).count();
+            if count > 0 {
+                return Err(Box::new(AccountError::UnreconciledBalances { count }));
+            }
+            return Ok(());
+        }
+        (&Mode::Explain(ref fund, date), _) => {
+            let mut sell_records = portfolio.make_sell_records(&fund_prices, tax_rates, sale_date, options)?;
+            sell_records.retain(|s| s.fund == fund && s.date_purchased == date);
+            if sell_records.is_empty() {
+                return Err(Box::new(AccountError::RecordNotFound { fund: fund.clone(), date }));
+            }
+            for (i, srec) in sell_records.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                print_explanation(srec, tax_rates, &args.output_options);
+            }
+            return Ok(());
+        }
+        (&Mode::Sell(target), [account]) if args.split_years.is_some() => {
+            let limit = args.split_years.unwrap();
+            let next_sale_date = args
+                .next_sale_date
+                .unwrap_or_else(|| chrono::NaiveDate::from_ymd(sale_date.year() + 1, 1, 1));
+            let year_limit = YearLimit {
+                cap_gains: limit,
+                next_sale_date,
+            };
+            let split = account.split_across_years(&fund_prices, target, &year_limit, tax_rates, sale_date, options)?;
+            print_year_split(split, target, tax_rates, &args.output_options);
+            return Ok(());
+        }
+        (&Mode::Sell(target), [account]) => {
+            let result = match args.method {
+                Method::MinimumCapGains => {
+                    account.minimum_cap_gains(&fund_prices, target, tax_rates, sale_date, options)?
+                }
+                Method::MinimumTax => account.minimize_tax(&fund_prices, target, tax_rates, sale_date, options)?,
+                Method::Fifo => account.fifo_sell(&fund_prices, target, tax_rates, sale_date, options)?,
+                Method::Lifo => account.lifo_sell(&fund_prices, target, tax_rates, sale_date, options)?,
+                Method::Hifo => account.hifo_sell(&fund_prices, target, tax_rates, sale_date, options)?,
+                Method::AverageCost => {
+                    account.average_cost_sell(&fund_prices, target, tax_rates, sale_date, options)?
+                }
+            };
+            (result, Some(target))
+        }
+        (&Mode::SellShares(ref fund, shares), [account]) => {
+            let result =
+                account.minimum_cap_gains_shares(&fund_prices, fund, shares, tax_rates, sale_date, options)?;
+            (result, None)
+        }
+        // only minimizing capital gains by amount is supported for several accounts, see Args::parse
+        (&Mode::Sell(target), _) => {
+            let result = portfolio.minimum_cap_gains(&fund_prices, target, tax_rates, sale_date, options)?;
+            (result, Some(target))
+        }
+        (&Mode::SellCapGains(cap_gains), [account]) => {
+            let result = account.target_cap_gains(&fund_prices, cap_gains, tax_rates, sale_date, options)?;
+            (result, None)
+        }
+        (&Mode::SellShares(..), _) | (&Mode::SellCapGains(..), _) | (&Mode::SolvePrice(..), _) => {
+            unreachable!("several accounts can only be used with --target")
+        }
+    };
+    if args.merge_lots {
+        result.records = merge_lots(result.records);
+    }
+    portfolio.flag_wash_sales(&mut result.records, sale_date);
+    if args.precision_check {
+        let drift = result.precision_drift();
+        if drift > PRECISION_TOLERANCE {
+            eprintln!(
+                "Warning: the totals differ by {:.4} when summed as floating-point numbers, so the account is \
+                 large enough for floating-point rounding to matter.",
+                drift
+            );
+        }
+    }
+    if let (true, Some(sell_target)) = (args.interactive, sell_target) {
+        let stdin = io::stdin();
+        let options = &args.output_options;
+        result = confirm_records(result, sell_target, tax_rates, options, stdin.lock(), io::stdout())?;
+    }
+
+    print_result(args, result, sell_target, tax_rates)
 }
 
-fn run(account_filename: &str, fundprice_filename: &str, sell_target: f64, tax_rate: f64) {
-    let account = load_account(account_filename).unwrap();
-    let fund_prices = load_fund_prices(fundprice_filename).unwrap();
+fn print_result(
+    args: &Args,
+    result: SellSummary,
+    sell_target: Option<f64>,
+    tax_rates: &TaxRates,
+) -> Result<(), Box<dyn error::Error>> {
+    let status = exit_summary(&result);
+    let mut output = open_output(args.output.as_ref())?;
+    match args.format {
+        Format::Table => {
+            let remaining = result.remaining.clone();
+            let mut s = String::new();
+            write_sell_summary(&mut s, result, sell_target, tax_rates, &args.output_options)?;
+            if args.show_remaining {
+                s.push('\n');
+                write_remaining(&mut s, &remaining, &args.output_options)?;
+            }
+            output.write_all(s.as_bytes())?;
+        }
+        Format::Json => write_sell_summary_json(&mut output, result, sell_target, &args.output_options)?,
+        Format::Csv => write_sell_summary_csv(&mut output, result, &args.output_options)?,
+        Format::Lots => write_lot_list(&mut output, result, &args.output_options)?,
+    }
+    output.flush()?;
+    // on stderr to be read by scripts whatever the output format
+    if !args.output_options.quiet {
+        eprintln!("{}", status);
+    }
+    Ok(())
+}
 
-    let result = account.minimum_cap_gains(&fund_prices, sell_target, tax_rate).unwrap();
-    print_sell_summary(result, tax_rate);
+/// Opens the file the sell summary is written to with --output, which is overwritten if it
+/// exists, or else stdout.
+fn open_output(filename: Option<&String>) -> io::Result<Box<dyn io::Write>> {
+    match filename {
+        Some(filename) => {
+            let file = fs::File::create(filename)
+                .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", filename, err)))?;
+            Ok(Box::new(io::BufWriter::new(file)))
+        }
+        None => Ok(Box::new(io::stdout())),
+    }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-
-    if args.len() < 4 {
-        println!("Calculate the records to sell to minimize capital gains.");
-        println!("usage: ./capgains <account_file> <fundprice_file> sell_target [tax_rate]");
-        println!("\naccount_file: csv file with the following fields -- Date,Fund,Transaction type,Shares transacted,Share price,Amount");
-        println!("fundprice_file: csv file with the following fields -- Fund,Share price");
-        println!("sell_target: Target amount to sell.");
-        println!("tax_rate: A flat tax rate to apply to capital gains. Taxes will be accounted for when selecting records to sell.");
-        println!("");
-        process::exit(1);
+/// Returns the exit status of `err`, as listed in `USAGE`.
+fn exit_code(err: &(dyn error::Error + 'static)) -> i32 {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        match err.downcast_ref() {
+            Some(&AccountError::InsufficientFunds { .. })
+            | Some(&AccountError::InsufficientFundsInLots { .. })
+            | Some(&AccountError::InsufficientShares { .. })
+            | Some(&AccountError::InsufficientCapGains { .. }) => return EXIT_INSUFFICIENT,
+            Some(&AccountError::MissingPrice { .. }) => return EXIT_MISSING_PRICE,
+            _ => source = err.source(),
+        }
+    }
+    if err.is::<capgains::Error>() || err.is::<io::Error>() {
+        EXIT_IO
+    } else {
+        EXIT_ERROR
     }
+}
 
-    let account_filename = &args[1];
-    let fundprice_filename = &args[2];
-    let sell_target = f64::from_str(&args[3]).unwrap();
+fn main() {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(ArgsError::Help) => {
+            print!("{}", USAGE);
+            process::exit(0);
+        }
+        Err(ArgsError::Invalid(msg)) => {
+            eprintln!("error: {}\n\nFor more information, try '--help'.", msg);
+            process::exit(EXIT_USAGE);
+        }
+    };
 
-    println!("Reading account information from: {}", account_filename);
-    println!("Reading fund price from: {}", fundprice_filename);
-    println!("Minimizing capital gains for target sell amount of: {}", sell_target);
+    log::set_level(args.log_level);
 
-    let mut tax_rate = 0.0;
-    if args.len() > 4 {
-        tax_rate = f64::from_str(&args[4]).unwrap();
-        println!("Applying a tax rate of {}%", 100.0*tax_rate);
+    // keep stdout clean for machine-readable output
+    if args.format == Format::Table {
+        for &(ref account, taxable) in &args.accounts {
+            let tax_free = if taxable { "" } else { " (tax-free)" };
+            println!("Reading account information from: {}{}", account, tax_free);
+        }
+        if let Some(ref fund_prices) = args.fund_prices {
+            println!("Reading fund price from: {}", fund_prices);
+        }
+        if let Some(ref splits) = args.splits {
+            println!("Reading stock splits from: {}", splits);
+        }
+        if let Some(ref fund_aliases) = args.fund_aliases {
+            println!("Reading fund aliases from: {}", fund_aliases);
+        }
+        match args.mode {
+            Mode::Sell(target) => {
+                let kind = match args.sell_options.target_kind {
+                    TargetKind::Net => "",
+                    TargetKind::Gross => " before taxes",
+                };
+                let target = if args.target_percent {
+                    format!(" {}% of the value of the portfolio", target)
+                } else {
+                    format!(": {}", target)
+                };
+                println!("{} for target sell amount{} of{}", args.method.description(), kind, target)
+            }
+            Mode::SellShares(ref fund, shares) => println!(
+                "{} for target sell shares of {}: {}",
+                args.method.description(),
+                fund,
+                shares
+            ),
+            Mode::SellCapGains(cap_gains) => println!("Selling for target capital gains of: {}", cap_gains),
+            Mode::Report => println!("Reporting unrealized capital gains"),
+            Mode::ListFunds => println!("Listing the unrealized capital gains of each fund"),
+            Mode::Reconcile(ref balances) => println!("Reconciling the records with the balances in: {}", balances),
+            Mode::Explain(ref fund, date) => println!("Explaining the sale of {} purchased on {}", fund, date),
+            Mode::SolvePrice(ref fund, target, ref dates) => {
+                let records = if dates.is_empty() { "all records" } else { "the records" };
+                println!("Finding the share price of {} at which selling {} reaches: {}", fund, records, target);
+                for date in dates {
+                    println!("Selling the record purchased on: {}", date);
+                }
+            }
+        }
+        let tax_rates = &args.tax_rates;
+        if let Some(ref brackets) = args.brackets {
+            println!("Applying the tax brackets from {} on top of an income of {}", brackets, args.income);
+        } else if !tax_rates.is_zero() {
+            if tax_rates.short_term == tax_rates.long_term {
+                println!("Applying a tax rate of {}%", 100.0*tax_rates.short_term);
+            } else {
+                println!(
+                    "Applying a short-term tax rate of {}% and a long-term tax rate of {}%",
+                    100.0*tax_rates.short_term, 100.0*tax_rates.long_term
+                );
+            }
+        }
+        let mut funds: Vec<_> = tax_rates.per_fund.iter().collect();
+        funds.sort_by_key(|&(fund, _)| fund);
+        for (fund, rate) in funds {
+            println!("Applying a tax rate of {}% to {}", 100.0*rate, fund);
+        }
+        if tax_rates.carryover_loss > 0.0 {
+            println!("Offsetting capital gains with a carryover loss of: {}", tax_rates.carryover_loss);
+        }
+        if let Some(date) = args.sell_options.no_sell_after {
+            println!("Not selling records purchased after: {}", date);
+        }
+        println!();
     }
-    println!("");
 
-    run(account_filename, fundprice_filename, sell_target, tax_rate);
+    if let Err(err) = run(&args) {
+        eprintln!("Error: {}", err);
+        if let (Some(date), Some(&AccountError::InsufficientFunds { .. })) =
+            (args.sell_options.no_sell_after, err.downcast_ref())
+        {
+            eprintln!("Note: records purchased after {} are not sold with '--no-sell-after'.", date);
+        }
+        let code = exit_code(err.as_ref());
+        if !args.output_options.quiet {
+            eprintln!("status=error code={}", code);
+        }
+        process::exit(code);
+    }
     process::exit(0);
 }
@@ -0,0 +1,164 @@
+//! Exact amounts of money, in whole cents.
+//!
+//! The amounts of the input files, and the amounts and capital gains of the records sold, are
+//! `Cents`, so they add up exactly however many records are summed. Prices and share counts are
+//! fractional and stay `f64`: the amount of selling some shares is their value rounded to cents,
+//! with halves rounded to even.
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use serde::{Serialize, Serializer};
+
+/// An amount of money as a whole number of cents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cents(i64);
+
+impl Cents {
+    pub const ZERO: Cents = Cents(0);
+
+    pub fn new(cents: i64) -> Self {
+        Cents(cents)
+    }
+
+    /// Rounds a dollar amount to cents, with halves rounded to even. Amounts beyond the range of
+    /// an `i64` of cents, about ±9.2e16 dollars, saturate.
+    pub fn from_dollars(dollars: f64) -> Self {
+        Cents((dollars*100.0).round_ties_even() as i64)
+    }
+
+    pub fn cents(self) -> i64 {
+        self.0
+    }
+
+    /// Returns the amount in dollars. This is exact up to about 9e13 dollars, past which an
+    /// `f64` can't hold every cent.
+    pub fn to_dollars(self) -> f64 {
+        self.0 as f64/100.0
+    }
+
+    pub fn abs(self) -> Self {
+        Cents(self.0.abs())
+    }
+
+    /// Returns -1.0, 0.0 or 1.0 for a negative, zero or positive amount.
+    pub fn signum(self) -> f64 {
+        self.0.signum() as f64
+    }
+}
+
+impl Add for Cents {
+    type Output = Cents;
+
+    fn add(self, other: Cents) -> Cents {
+        Cents(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Cents {
+    fn add_assign(&mut self, other: Cents) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for Cents {
+    type Output = Cents;
+
+    fn sub(self, other: Cents) -> Cents {
+        Cents(self.0 - other.0)
+    }
+}
+
+impl SubAssign for Cents {
+    fn sub_assign(&mut self, other: Cents) {
+        self.0 -= other.0;
+    }
+}
+
+impl Neg for Cents {
+    type Output = Cents;
+
+    fn neg(self) -> Cents {
+        Cents(-self.0)
+    }
+}
+
+/// Scales an amount, e.g. to the part of a lot that's kept, rounded to cents with halves to
+/// even.
+impl Mul<f64> for Cents {
+    type Output = Cents;
+
+    fn mul(self, factor: f64) -> Cents {
+        Cents((self.0 as f64*factor).round_ties_even() as i64)
+    }
+}
+
+impl Sum for Cents {
+    fn sum<I: Iterator<Item = Cents>>(iter: I) -> Cents {
+        iter.fold(Cents::ZERO, Add::add)
+    }
+}
+
+/// Writes the amount in dollars with two decimals, e.g. "-1234.50", or with the precision of the
+/// format, e.g. "{:.0}".
+impl fmt::Display for Cents {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.precision().is_some() {
+            return fmt::Display::fmt(&self.to_dollars(), f);
+        }
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        f.pad(&format!("{}{}.{:02}", sign, cents/100, cents%100))
+    }
+}
+
+/// Parses an amount in dollars such as "1234.56" or "-0.5" exactly, without going through an
+/// `f64`. Digits past the cents are rounded with halves to even. Amounts written with an
+/// exponent, e.g. "1.5e3", are read as an `f64` and then rounded.
+impl FromStr for Cents {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, unsigned) = match s.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (whole, fraction) = match unsigned.find('.') {
+            Some(i) => (&unsigned[..i], &unsigned[i + 1..]),
+            None => (unsigned, ""),
+        };
+        let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if !is_digits(whole) || !is_digits(fraction) || (whole.is_empty() && fraction.is_empty()) {
+            let dollars = f64::from_str(s).map_err(|err| err.to_string())?;
+            if !dollars.is_finite() || dollars.abs() >= i64::MAX as f64/100.0 {
+                return Err(format!("invalid amount '{}', expected a finite number", s));
+            }
+            return Ok(Cents::from_dollars(dollars));
+        }
+
+        let too_large = || format!("invalid amount '{}', too large", s);
+        let digit = |i: usize| fraction.as_bytes().get(i).map_or(0, |&b| i64::from(b - b'0'));
+        let whole: i64 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| too_large())? };
+        let mut cents = whole
+            .checked_mul(100)
+            .and_then(|c| c.checked_add(digit(0)*10 + digit(1)))
+            .ok_or_else(too_large)?;
+        // round the digits past the cents, with a half to the even cent
+        let past = fraction.get(2..).unwrap_or("");
+        let first = digit(2);
+        let rest_is_zero = past.bytes().skip(1).all(|b| b == b'0');
+        if first > 5 || (first == 5 && (!rest_is_zero || cents % 2 == 1)) {
+            cents = cents.checked_add(1).ok_or_else(too_large)?;
+        }
+        Ok(Cents(if negative { -cents } else { cents }))
+    }
+}
+
+/// Serializes the amount as a number of dollars.
+impl Serialize for Cents {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_dollars())
+    }
+}
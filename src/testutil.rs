@@ -0,0 +1,114 @@
+//! Reproducible synthetic accounts, for tests and benchmarks. The same number of lots and seed
+//! always give the same records and prices.
+
+use std::collections::HashMap;
+use std::io;
+
+use chrono;
+use csv;
+
+use money::Cents;
+use Record;
+
+/// Funds of the generated records, with the price each is generated around.
+pub const FUNDS: [(&str, f64); 4] = [("VTSAX", 70.0), ("VTIAX", 27.0), ("VBTLX", 10.5), ("VTABX", 21.5)];
+
+/// A small, seeded pseudorandom number generator (SplitMix64). It is not suitable for anything
+/// but test data.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in [0, 1).
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a number in [0, n), for n > 0.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+fn cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+/// Generates `lots` purchases of the funds in `FUNDS` between 2000 and 2017, and a current
+/// price for each fund. Purchase prices are within 50% of the fund's price, so some lots are
+/// held at a loss, and amounts are rounded to cents. Records are ordered by date.
+pub fn generate(lots: usize, seed: u64) -> (Vec<Record>, HashMap<String, f64>) {
+    let mut rng = Rng::new(seed);
+    let start = chrono::NaiveDate::from_ymd(2000, 1, 3);
+    let days = chrono::NaiveDate::from_ymd(2017, 12, 29).signed_duration_since(start).num_days();
+
+    let mut records: Vec<Record> = (0..lots)
+        .map(|_| {
+            let (fund, price) = FUNDS[rng.below(FUNDS.len() as u64) as usize];
+            let num_shares = 1.0 + rng.below(100_000) as f64 / 1000.0;
+            let share_price = cents(price * (0.5 + rng.next_f64()));
+            Record {
+                date: start + chrono::Duration::days(rng.below(days as u64 + 1) as i64),
+                fund: fund.to_string(),
+                transaction_type: "Buy".to_string(),
+                num_shares,
+                share_price,
+                amount: Cents::from_dollars(num_shares * share_price),
+                fee: 0.0,
+                source_row: None,
+            }
+        })
+        .collect();
+    records.sort_by_key(|r| r.date);
+    // the rows the records have when written with `write_account`
+    for (i, record) in records.iter_mut().enumerate() {
+        record.source_row = Some(i as u64 + 1);
+    }
+
+    let fund_prices = FUNDS.iter().map(|&(fund, price)| (fund.to_string(), price)).collect();
+    (records, fund_prices)
+}
+
+/// Writes `records` as an account file.
+pub fn write_account<W: io::Write>(w: W, records: &[Record]) -> csv::Result<()> {
+    let mut wtr = csv::Writer::from_writer(w);
+    wtr.write_record(["Date", "Fund", "Transaction type", "Shares transacted", "Share price", "Amount"])?;
+    for r in records {
+        wtr.write_record([
+            r.date.format("%Y-%m-%d").to_string(),
+            r.fund.clone(),
+            r.transaction_type.clone(),
+            r.num_shares.to_string(),
+            r.share_price.to_string(),
+            r.amount.to_string(),
+        ])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Writes `fund_prices` as a fund prices file, ordered by fund.
+pub fn write_fund_prices<W: io::Write>(w: W, fund_prices: &HashMap<String, f64>) -> csv::Result<()> {
+    let mut funds: Vec<_> = fund_prices.iter().collect();
+    funds.sort_by_key(|&(fund, _)| fund);
+
+    let mut wtr = csv::Writer::from_writer(w);
+    wtr.write_record(["Fund", "Share price"])?;
+    for (fund, price) in funds {
+        wtr.write_record([fund.clone(), price.to_string()])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
@@ -0,0 +1,33 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, SellOptions, TaxRates};
+
+use common::{date, record};
+
+#[test]
+fn shares_are_sold_at_average_cost_oldest_first() {
+    // 20 VTSAX shares cost $1300 in total, so each has a basis of $65.
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 10.0, 80.0),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let options = SellOptions::default();
+    let result = account
+        .average_cost_sell(&fund_prices, 1500.0, &TaxRates::flat(0.0), date("2018-01-02"), &options)
+        .unwrap()
+        .records;
+
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].date_purchased, date("2015-01-02"));
+    assert_eq!(result[0].num_shares, 10.0);
+    assert_eq!(result[1].num_shares, 5.0);
+    assert!(result.iter().all(|s| s.share_price_purchased == 65.0));
+    assert_eq!(result.iter().map(|s| s.cap_gains.to_dollars()).sum::<f64>(), 525.0);
+}
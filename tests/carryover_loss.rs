@@ -0,0 +1,68 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, SellOptions, TaxRates};
+
+use common::{date, record};
+
+fn tax_rates(carryover_loss: f64) -> TaxRates {
+    TaxRates {
+        carryover_loss,
+        ..TaxRates::flat(0.2)
+    }
+}
+
+fn fund_prices() -> HashMap<String, f64> {
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices
+}
+
+#[test]
+fn carryover_loss_offsets_gains_before_taxes() {
+    // $500 of gains, of which $300 are offset
+    let account = Account::new(vec![record("2015-01-02", "VTSAX", 10.0, 50.0)]);
+    let fund_prices = fund_prices();
+    let summary = account
+        .minimum_cap_gains(&fund_prices, 960.0, &tax_rates(300.0), date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    assert_eq!(summary.carryover_applied, 300.0);
+    assert_eq!(summary.total_tax, 40.0);
+    assert_eq!(summary.net_amount, 960.0);
+}
+
+#[test]
+fn carryover_loss_does_not_make_gains_negative() {
+    let account = Account::new(vec![record("2015-01-02", "VTSAX", 10.0, 50.0)]);
+    let fund_prices = fund_prices();
+    let summary = account
+        .minimum_cap_gains(&fund_prices, 1000.0, &tax_rates(3000.0), date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    assert_eq!(summary.carryover_applied, 500.0);
+    assert_eq!(summary.total_tax, 0.0);
+    assert_eq!(summary.net_amount, 1000.0);
+}
+
+#[test]
+fn carryover_loss_reduces_the_shares_sold() {
+    // without the carryover, each share nets $90 and 12 shares are needed for $1000
+    let account = Account::new(vec![record("2015-01-02", "VTSAX", 20.0, 50.0)]);
+    let fund_prices = fund_prices();
+    let options = SellOptions::default();
+    let sell = |carryover_loss: f64| {
+        account
+            .minimum_cap_gains(&fund_prices, 1000.0, &tax_rates(carryover_loss), date("2018-01-02"), &options)
+            .unwrap()
+            .records[0]
+            .num_shares
+    };
+
+    assert_eq!(sell(0.0), 12.0);
+    assert_eq!(sell(500.0), 10.0);
+}
@@ -0,0 +1,83 @@
+#![allow(dead_code)]
+
+use capgains::money::Cents;
+use capgains::Record;
+
+pub fn date(s: &str) -> chrono::NaiveDate {
+    chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+pub fn transaction(
+    date_purchased: &str,
+    fund: &str,
+    transaction_type: &str,
+    num_shares: f64,
+    share_price: f64,
+) -> Record {
+    Record {
+        date: date(date_purchased),
+        fund: fund.to_string(),
+        transaction_type: transaction_type.to_string(),
+        num_shares,
+        share_price,
+        amount: Cents::from_dollars(num_shares * share_price),
+        fee: 0.0,
+        source_row: None,
+    }
+}
+
+pub fn record(date_purchased: &str, fund: &str, num_shares: f64, share_price: f64) -> Record {
+    transaction(date_purchased, fund, "Buy", num_shares, share_price)
+}
+
+/// Returns the path of the file `name` in tests/fixtures.
+pub fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+/// A file in the temporary directory, named after the test process, that is deleted when it is
+/// dropped, even if the test fails.
+pub struct TempFile {
+    path: String,
+}
+
+impl TempFile {
+    /// Returns the temporary file `name`, which is not created. The tests of a file run at the
+    /// same time, so each needs a name of its own. The extension of `name` is kept.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("capgains-{}-{}", std::process::id(), name));
+        TempFile {
+            path: path.to_str().unwrap().to_string(),
+        }
+    }
+
+    /// Returns the temporary file `name` written with `contents`.
+    pub fn with_contents<C: AsRef<[u8]>>(name: &str, contents: C) -> Self {
+        let file = TempFile::new(name);
+        std::fs::write(&file.path, contents).unwrap();
+        file
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        // the file may not have been created
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Compares `actual` with the contents of the file `name` in tests/golden. Set UPDATE_GOLDEN to
+/// write `actual` to the file instead, e.g. after an intended change of the output.
+pub fn assert_golden(name: &str, actual: &str) {
+    let path = format!("{}/tests/golden/{}", env!("CARGO_MANIFEST_DIR"), name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|err| panic!("{}: {}", path, err));
+    assert_eq!(actual, expected, "output differs from {}", path);
+}
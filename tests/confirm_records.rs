@@ -0,0 +1,63 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{confirm_records, Account, OutputOptions, SellOptions, TaxRates};
+
+use common::{date, record};
+
+fn account() -> Account {
+    Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 10.0, 80.0),
+    ])
+}
+
+fn fund_prices() -> HashMap<String, f64> {
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices
+}
+
+#[test]
+fn declined_records_are_kept() {
+    let fund_prices = fund_prices();
+    let tax_rates = TaxRates::flat(0.0);
+    let summary =
+        account().minimum_cap_gains(&fund_prices, 1500.0, &tax_rates, date("2018-01-02"), &SellOptions::default());
+    let mut output = Vec::new();
+    let summary = confirm_records(
+        summary.unwrap(),
+        1500.0,
+        &tax_rates,
+        &OutputOptions::default(),
+        "maybe\nn\ny\n".as_bytes(),
+        &mut output,
+    )
+    .unwrap();
+
+    assert_eq!(summary.records.len(), 1);
+    assert_eq!(summary.records[0].date_purchased, date("2015-01-02"));
+    assert_eq!(summary.total_amount, 500.0);
+    assert_eq!(summary.remaining.len(), 2);
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Please answer y or n."), "{}", output);
+    assert!(output.contains("  total: $0.00 of the $1500.00 target"), "{}", output);
+    assert!(output.contains("  total: $500.00 of the $1500.00 target"), "{}", output);
+}
+
+#[test]
+fn running_out_of_answers_is_an_error() {
+    let fund_prices = fund_prices();
+    let tax_rates = TaxRates::flat(0.0);
+    let summary =
+        account().minimum_cap_gains(&fund_prices, 1500.0, &tax_rates, date("2018-01-02"), &SellOptions::default());
+    let options = OutputOptions::default();
+    let result = confirm_records(summary.unwrap(), 1500.0, &tax_rates, &options, "y\n".as_bytes(), Vec::new());
+
+    assert!(result.is_err());
+}
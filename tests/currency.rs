@@ -0,0 +1,60 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use capgains::money::Cents;
+use capgains::{load_account, load_fund_prices, Currency, InputOptions};
+
+use common::TempFile;
+
+fn options(currency: &str) -> InputOptions {
+    InputOptions {
+        currency: currency.parse().unwrap(),
+        ..InputOptions::default()
+    }
+}
+
+#[test]
+fn euro_amounts_are_read() {
+    let file = TempFile::with_contents(
+        "eur-account.csv",
+        "Date,Fund,Transaction type,Shares transacted,Share price,Amount\n\
+         2015-01-02,VTSAX,Buy,\"1.000,5\",\"50,25\",\"50.275,13 €\"\n",
+    );
+    let account = load_account(file.path(), &options("EUR")).ok().unwrap();
+
+    let record = &account.holdings().unwrap()[0];
+    assert_eq!(record.num_shares, 1000.5);
+    assert_eq!(record.share_price, 50.25);
+    assert_eq!(record.amount, Cents::new(5027513));
+}
+
+#[test]
+fn euro_prices_are_read() {
+    let file = TempFile::with_contents("eur-prices.csv", "Fund,Share price\nVTSAX,\"€1.100,50\"\n");
+    let result = load_fund_prices(file.path(), &options("eur"));
+
+    assert_eq!(result.unwrap()["VTSAX"], 1100.5);
+}
+
+#[test]
+fn amounts_are_printed_with_the_symbol() {
+    let currency: Currency = "GBP".parse().unwrap();
+
+    assert_eq!(currency.format(1234.5, 2), "£1234.50");
+    assert_eq!(currency.format(-3.0, 0), "-£3");
+    assert!("XYZ".parse::<Currency>().is_err());
+}
+
+#[test]
+fn grouped_amounts_have_thousands_separators() {
+    let usd = Currency::default();
+    assert_eq!(usd.format_grouped(123456.784, 2), "$123,456.78");
+    assert_eq!(usd.format_grouped(-1234567.0, 0), "-$1,234,567");
+    assert_eq!(usd.format_grouped(999.999, 2), "$1,000.00");
+    assert_eq!(usd.format_grouped(12.5, 2), "$12.50");
+
+    let eur: Currency = "EUR".parse().unwrap();
+    assert_eq!(eur.format_grouped(1234.5, 2), "€1.234,50");
+}
@@ -0,0 +1,100 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, SellOptions, TaxRates};
+
+use common::{date, record};
+
+#[test]
+fn equal_ratios_are_sold_in_a_fixed_order() {
+    // every lot has a capital gains ratio of 0.5
+    let account = Account::new(vec![
+        record("2016-01-04", "VTSAX", 10.0, 50.0),
+        record("2015-01-02", "VTIAX", 10.0, 15.0),
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2015-01-02", "VBTLX", 100.0, 5.0),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices.insert("VTIAX".to_string(), 30.0);
+    fund_prices.insert("VBTLX".to_string(), 10.0);
+
+    let options = SellOptions::default();
+    let sell = || {
+        account
+            .minimum_cap_gains(&fund_prices, 1500.0, &TaxRates::flat(0.2), date("2018-01-02"), &options)
+            .unwrap()
+            .records
+    };
+    let result = sell();
+    assert_eq!(result, sell());
+
+    let lots: Vec<_> = result.iter().map(|s| (s.date_purchased, s.fund)).collect();
+    assert_eq!(
+        lots,
+        vec![
+            (date("2015-01-02"), "VBTLX"),
+            (date("2015-01-02"), "VTIAX"),
+            (date("2015-01-02"), "VTSAX"),
+        ]
+    );
+}
+
+#[test]
+fn records_are_sold_in_ranked_order() {
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 10.0, 80.0),
+        record("2017-01-03", "VTSAX", 10.0, 120.0),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let tax_rates = TaxRates::flat(0.0);
+    let sale_date = date("2018-01-02");
+    let options = SellOptions::default();
+
+    let ranked = account.ranked_sell_records(&fund_prices, &tax_rates, sale_date, &options).unwrap();
+    let dates: Vec<_> = ranked.iter().map(|s| s.date_purchased).collect();
+    assert_eq!(dates, vec![date("2017-01-03"), date("2016-01-04"), date("2015-01-02")]);
+
+    let summary = account.minimum_cap_gains(&fund_prices, 2000.0, &tax_rates, sale_date, &options).unwrap();
+    assert_eq!(summary.records, ranked[..2].to_vec());
+}
+
+#[test]
+fn sell_records_are_in_the_order_of_the_holdings() {
+    // enough lots to be split over several threads with the "parallel" feature
+    let records = (0..1000).map(|i| record("2015-01-02", "VTSAX", 1.0 + i as f64, 50.0)).collect();
+    let account = Account::new(records);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+
+    let sell_records = account
+        .make_sell_records(&fund_prices, &TaxRates::flat(0.2), date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+    let shares: Vec<_> = sell_records.iter().map(|s| s.num_shares).collect();
+    assert_eq!(shares, (0..1000).map(|i| 1.0 + i as f64).collect::<Vec<_>>());
+}
+
+#[test]
+fn long_term_records_are_sold_first_for_nearly_equal_ratios() {
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        // short-term with a ratio lower only by rounding error
+        record("2017-09-01", "VTSAX", 10.0, 50.000_000_1),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+
+    let result = account
+        .minimum_cap_gains(&fund_prices, 500.0, &TaxRates::flat(0.2), date("2018-01-02"), &SellOptions::default())
+        .unwrap()
+        .records;
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].date_purchased, date("2015-01-02"));
+    assert!(result[0].is_long_term);
+}
@@ -0,0 +1,69 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::process::{Command, Output};
+
+use common::fixture;
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_capgains"))
+        .args(["--account", &fixture("account.csv"), "--as-of", "2018-01-02"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn exit_code(args: &[&str]) -> i32 {
+    run(args).status.code().unwrap()
+}
+
+fn status_line(args: &[&str]) -> String {
+    String::from_utf8(run(args).stderr).unwrap().lines().last().unwrap_or("").to_string()
+}
+
+#[test]
+fn success_is_zero() {
+    assert_eq!(exit_code(&["--fund-prices", &fixture("fund_prices.csv"), "--target", "500"]), 0);
+}
+
+#[test]
+fn invalid_arguments_are_two() {
+    assert_eq!(exit_code(&["--fund-prices", &fixture("fund_prices.csv"), "--target", "much"]), 2);
+}
+
+#[test]
+fn unreadable_files_are_three() {
+    assert_eq!(exit_code(&["--fund-prices", &fixture("missing.csv"), "--target", "500"]), 3);
+}
+
+#[test]
+fn insufficient_funds_are_four() {
+    assert_eq!(exit_code(&["--fund-prices", &fixture("fund_prices.csv"), "--target", "1000000"]), 4);
+}
+
+#[test]
+fn missing_prices_are_five() {
+    assert_eq!(exit_code(&["--price", "VTSAX=100", "--target", "500"]), 5);
+}
+
+#[test]
+fn status_line_has_the_totals_whatever_the_format() {
+    let prices = fixture("fund_prices.csv");
+    let status = "status=ok amount=504.00 cap_gains=-24.00 tax=0.00 net=504.00";
+    assert_eq!(status_line(&["--fund-prices", &prices, "--target", "500"]), status);
+    assert_eq!(status_line(&["--fund-prices", &prices, "--target", "500", "--format", "json"]), status);
+}
+
+#[test]
+fn status_line_has_the_exit_code_on_errors() {
+    let args = ["--fund-prices", &fixture("fund_prices.csv"), "--target", "1000000"];
+    assert_eq!(status_line(&args), "status=error code=4");
+}
+
+#[test]
+fn quiet_leaves_out_the_status_line() {
+    let output = run(&["--fund-prices", &fixture("fund_prices.csv"), "--target", "500", "--quiet"]);
+    assert!(output.stderr.is_empty());
+}
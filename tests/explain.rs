@@ -0,0 +1,46 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{write_explanation, Account, OutputOptions, SellOptions, TaxRates};
+
+use common::{date, record};
+
+fn explain(taxable: bool) -> String {
+    let account = Account::new(vec![record("2016-01-04", "VTSAX", 10.0, 80.0)]).with_taxable(taxable);
+    let tax_rates = &TaxRates::flat(0.2);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let sell_records = account
+        .make_sell_records(&fund_prices, tax_rates, date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    let mut output = String::new();
+    write_explanation(&mut output, &sell_records[0], tax_rates, &OutputOptions::default()).unwrap();
+    output
+}
+
+#[test]
+fn explanation_shows_each_step() {
+    let output = explain(true);
+
+    assert!(output.starts_with("Selling the record of VTSAX purchased on 2016-01-04, on 2018-01-02:\n"));
+    assert!(output.contains("  amount:         10 shares * $100.00 = $1000.00\n"));
+    assert!(output.contains("  cost basis:     10 shares * $80.00 = $800.00\n"));
+    assert!(output.contains("  cap gains:      $1000.00 - $800.00 = $200.00\n"));
+    assert!(output.contains("  cg ratio:       $200.00 / $1000.00 = 0.2000\n"));
+    assert!(output.contains("  held:           729 days, long term\n"));
+    assert!(output.contains("  taxes:          $200.00 * 20.00% = $40.00\n"));
+    assert!(output.ends_with("  net amount:     $1000.00 - $40.00 = $960.00\n"));
+}
+
+#[test]
+fn explanation_of_a_tax_free_account() {
+    let output = explain(false);
+
+    assert!(output.contains("  taxes:          none, the account is tax-free\n"));
+    assert!(output.ends_with("  net amount:     $1000.00 - $0.00 = $1000.00\n"));
+}
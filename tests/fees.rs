@@ -0,0 +1,165 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+use std::process::Command;
+
+use capgains::money::Cents;
+use capgains::{Account, SellOptions, TargetKind, TaxRates};
+
+use common::{date, record, transaction, TempFile};
+
+fn fund_prices() -> HashMap<String, f64> {
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices
+}
+
+#[test]
+fn fees_add_to_cost_basis() {
+    let mut buy = record("2015-01-02", "VTSAX", 10.0, 50.0);
+    buy.fee = 10.0;
+    let account = Account::new(vec![buy]);
+    let fund_prices = fund_prices();
+    let result = account
+        .make_sell_records(&fund_prices, &TaxRates::flat(0.0), date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    assert_eq!(result[0].share_price_purchased, 51.0);
+    assert_eq!(result[0].cap_gains.to_dollars(), 490.0);
+}
+
+#[test]
+fn fees_are_reduced_with_the_lot() {
+    let mut buy = record("2015-01-02", "VTSAX", 10.0, 50.0);
+    buy.fee = 10.0;
+    let account = Account::new(vec![buy, transaction("2016-01-04", "VTSAX", "Sell", -5.0, 60.0)]);
+    let fund_prices = fund_prices();
+    let result = account
+        .make_sell_records(&fund_prices, &TaxRates::flat(0.0), date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    assert_eq!(result[0].num_shares, 5.0);
+    assert_eq!(result[0].share_price_purchased, 51.0);
+    assert_eq!(result[0].cap_gains.to_dollars(), 245.0);
+}
+
+#[test]
+fn disposal_fees_reduce_the_proceeds_of_a_wash_sale() {
+    // 10 shares bought at 100 are sold at 80 less a fee of 20 and bought back at 85
+    let mut sell = transaction("2017-06-01", "VTSAX", "Sell", -10.0, 80.0);
+    sell.fee = 20.0;
+    let records = vec![record("2017-01-03", "VTSAX", 10.0, 100.0), sell, record("2017-06-15", "VTSAX", 10.0, 85.0)];
+    let account = Account::new(records).with_wash_sale_adjustment(true);
+
+    let holdings = account.holdings().unwrap();
+    assert_eq!(holdings.len(), 1);
+    // the disallowed loss is 22 a share, 2 of it the fee
+    assert_eq!(holdings[0].share_price, 107.0);
+}
+
+fn sale_fee(sale_fee: f64) -> SellOptions {
+    SellOptions {
+        sale_fee,
+        ..SellOptions::default()
+    }
+}
+
+#[test]
+fn sale_fees_are_taken_from_the_amount() {
+    let account = Account::new(vec![record("2015-01-02", "VTSAX", 10.0, 50.0)]);
+    let fund_prices = fund_prices();
+    let result = account
+        .make_sell_records(&fund_prices, &TaxRates::flat(0.0), date("2018-01-02"), &sale_fee(5.0))
+        .unwrap();
+
+    assert_eq!(result[0].sale_fee.to_dollars(), 5.0);
+    assert_eq!(result[0].amount.to_dollars(), 995.0);
+    assert_eq!(result[0].cap_gains.to_dollars(), 495.0);
+}
+
+#[test]
+fn partially_sold_record_sells_enough_to_pay_the_fee() {
+    let account = Account::new(vec![record("2015-01-02", "VTSAX", 10.0, 50.0)]);
+    let fund_prices = fund_prices();
+    let tax_rates = TaxRates::flat(0.2);
+    let sale_date = date("2018-01-02");
+
+    let gross = SellOptions {
+        target_kind: TargetKind::Gross,
+        ..sale_fee(5.0)
+    };
+    let summary = account.minimum_cap_gains(&fund_prices, 500.0, &tax_rates, sale_date, &gross).unwrap();
+    assert_eq!(summary.records[0].num_shares, 6.0);
+    assert_eq!((summary.total_amount, summary.total_cap_gains), (595.0, 295.0));
+    // the rest pays its own fee when it's sold
+    assert_eq!(summary.remaining[0].amount.to_dollars(), 395.0);
+
+    // after taxes, n shares bring in 100n - 5 - 0.2(50n - 5) = 90n - 4
+    let summary = account.minimum_cap_gains(&fund_prices, 500.0, &tax_rates, sale_date, &sale_fee(5.0)).unwrap();
+    assert_eq!(summary.records[0].num_shares, 6.0);
+    assert_eq!(summary.net_amount, 536.0);
+}
+
+#[test]
+fn sale_fees_lower_the_capital_gains_of_a_target() {
+    let account = Account::new(vec![record("2015-01-02", "VTSAX", 10.0, 50.0)]);
+    let fund_prices = fund_prices();
+    let summary = account
+        .target_cap_gains(&fund_prices, 200.0, &TaxRates::flat(0.0), date("2018-01-02"), &sale_fee(5.0))
+        .unwrap();
+
+    assert_eq!(summary.records[0].num_shares, 5.0);
+    assert_eq!(summary.total_cap_gains, 245.0);
+}
+
+#[test]
+fn amount_can_be_the_cost_basis() {
+    // the amount includes costs the share price leaves out
+    let mut buy = record("2015-01-02", "VTSAX", 10.0, 50.0);
+    buy.amount = Cents::from_dollars(507.5);
+    let account = Account::new(vec![buy, transaction("2016-01-04", "VTSAX", "Sell", -2.0, 60.0)]);
+    let fund_prices = fund_prices();
+    let tax_rates = TaxRates::flat(0.0);
+    let sale_date = date("2018-01-02");
+
+    let result = account.make_sell_records(&fund_prices, &tax_rates, sale_date, &SellOptions::default()).unwrap();
+    assert_eq!(result[0].share_price_purchased, 50.0);
+
+    let options = SellOptions {
+        basis_from_amount: true,
+        ..SellOptions::default()
+    };
+    let result = account.make_sell_records(&fund_prices, &tax_rates, sale_date, &options).unwrap();
+    assert_eq!(result[0].num_shares, 8.0);
+    assert_eq!(result[0].share_price_purchased, 50.75);
+    assert_eq!(result[0].cap_gains.to_dollars(), 394.0);
+}
+
+#[test]
+fn lots_worth_no_more_than_the_sale_fee_are_not_sold_by_any_method() {
+    // the first lot is worth the fee and the second less, so only the third can be sold
+    let account = TempFile::with_contents(
+        "fee-account.csv",
+        "Date,Fund,Transaction type,Shares transacted,Share price,Amount\n\
+         2015-01-02,VTSAX,Buy,1,50,50\n\
+         2015-06-01,VTSAX,Buy,0.5,60,30\n\
+         2016-01-04,VTSAX,Buy,10,70,700\n",
+    );
+    for method in &["mincg", "mintax", "fifo", "lifo", "hifo", "avgcost"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_capgains"))
+            .args(["--account", account.path(), "--price", "VTSAX=70", "--as-of", "2018-01-02"])
+            .args(["--target", "10", "--sale-fee", "70", "--method", method, "--format", "json"])
+            .output()
+            .unwrap();
+        let json = String::from_utf8(output.stdout).unwrap();
+
+        assert!(output.status.success(), "{}: {}", method, String::from_utf8_lossy(&output.stderr));
+        // 2 shares of the third lot bring in 140 less the fee
+        let sold = "\"date_purchased\":\"2016-01-04\",\"fund\":\"VTSAX\",\"num_shares\":2,";
+        assert!(json.contains(sold), "{}: {}", method, json);
+        assert!(json.contains("],\"amount\":70,"), "{}: {}", method, json);
+    }
+}
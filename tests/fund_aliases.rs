@@ -0,0 +1,60 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{load_fund_aliases, Account, AccountError, InputOptions, SellOptions, TaxRates};
+
+use common::{date, record, TempFile};
+
+fn aliases() -> HashMap<String, String> {
+    let mut aliases = HashMap::new();
+    aliases.insert("Vanguard Total Stock".to_string(), "VTSAX".to_string());
+    aliases
+}
+
+#[test]
+fn records_of_an_alias_are_priced_by_their_fund() {
+    let account = Account::new(vec![
+        record("2015-01-02", "Vanguard Total Stock", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 10.0, 80.0),
+    ])
+    .with_fund_aliases(&aliases());
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let sell_records = account
+        .make_sell_records(&fund_prices, &TaxRates::flat(0.0), date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    assert!(sell_records.iter().all(|s| s.fund == "VTSAX"));
+    assert_eq!(sell_records.iter().map(|s| s.num_shares).sum::<f64>(), 20.0);
+}
+
+#[test]
+fn fund_without_an_alias_or_price_is_still_missing_a_price() {
+    let account = Account::new(vec![record("2015-01-02", "Vanguard Total Bond", 10.0, 50.0)])
+        .with_fund_aliases(&aliases());
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let options = SellOptions::default();
+    let result = account.make_sell_records(&fund_prices, &TaxRates::flat(0.0), date("2018-01-02"), &options);
+
+    assert_eq!(
+        result,
+        Err(AccountError::MissingPrice {
+            fund: "Vanguard Total Bond".to_string()
+        })
+    );
+}
+
+#[test]
+fn aliases_are_loaded_from_a_file() {
+    let file = TempFile::with_contents("aliases.csv", "Alias,Fund\nVanguard Total Stock,VTSAX\nVanguard Total Bond,VBTLX\n");
+    let result = load_fund_aliases(file.path(), &InputOptions::default());
+
+    let aliases = result.unwrap();
+    assert_eq!(aliases.len(), 2);
+    assert_eq!(aliases["Vanguard Total Bond"], "VBTLX");
+}
@@ -0,0 +1,49 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, AccountError, FundFilter, SellOptions, TaxRates};
+
+use common::{date, record};
+
+fn account() -> Account {
+    Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VBTLX", 100.0, 20.0),
+    ])
+}
+
+fn options(funds: FundFilter) -> SellOptions {
+    SellOptions {
+        funds,
+        ..SellOptions::default()
+    }
+}
+
+#[test]
+fn excluded_funds_are_not_sold_and_need_no_price() {
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let options = options(FundFilter::Exclude(vec!["VBTLX".to_string()].into_iter().collect()));
+    let result = account()
+        .minimum_cap_gains(&fund_prices, 500.0, &TaxRates::flat(0.0), date("2018-01-02"), &options)
+        .unwrap()
+        .records;
+
+    assert!(result.iter().all(|s| s.fund == "VTSAX"));
+    assert_eq!(result.iter().map(|s| s.num_shares).sum::<f64>(), 5.0);
+}
+
+#[test]
+fn included_fund_without_price_is_an_error() {
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let options = options(FundFilter::Only(vec!["VBTLX".to_string()].into_iter().collect()));
+    let result =
+        account().minimum_cap_gains(&fund_prices, 500.0, &TaxRates::flat(0.0), date("2018-01-02"), &options);
+
+    assert_eq!(result, Err(AccountError::MissingPrice { fund: "VBTLX".to_string() }));
+}
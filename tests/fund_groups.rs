@@ -0,0 +1,77 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{write_sell_summary, Account, FundFilter, OutputOptions, SellOptions, TaxRates};
+
+use common::{date, record};
+
+fn account() -> Account {
+    let mut fund_groups = HashMap::new();
+    fund_groups.insert("VTSAX".to_string(), "total-stock".to_string());
+    fund_groups.insert("VTSMX".to_string(), "total-stock".to_string());
+    Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2015-01-02", "VTSMX", 10.0, 50.0),
+        record("2016-01-04", "VBTLX", 100.0, 10.0),
+    ])
+    .with_fund_groups(&fund_groups)
+}
+
+fn fund_prices() -> HashMap<String, f64> {
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices.insert("VTSMX".to_string(), 99.0);
+    fund_prices.insert("VBTLX".to_string(), 11.0);
+    fund_prices
+}
+
+fn funds(filter: FundFilter) -> Vec<String> {
+    let options = SellOptions {
+        funds: filter,
+        ..SellOptions::default()
+    };
+    let fund_prices = fund_prices();
+    let sell_records = account()
+        .make_sell_records(&fund_prices, &TaxRates::flat(0.0), date("2018-01-02"), &options)
+        .unwrap();
+    sell_records.iter().map(|s| s.fund.to_string()).collect()
+}
+
+#[test]
+fn share_classes_keep_their_own_price() {
+    let fund_prices = fund_prices();
+    let sell_records = account()
+        .make_sell_records(&fund_prices, &TaxRates::flat(0.0), date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    assert_eq!(sell_records[0].share_price, 100.0);
+    assert_eq!(sell_records[1].share_price, 99.0);
+    assert_eq!(sell_records[1].fund_group, Some("total-stock".to_string()));
+    assert_eq!(sell_records[2].fund_group, None);
+}
+
+#[test]
+fn a_fund_filter_with_the_group_applies_to_every_class() {
+    let group = || vec!["total-stock".to_string()].into_iter().collect();
+
+    assert_eq!(funds(FundFilter::Only(group())), vec!["VTSAX", "VTSMX"]);
+    assert_eq!(funds(FundFilter::Exclude(group())), vec!["VBTLX"]);
+    assert_eq!(funds(FundFilter::Only(vec!["VTSMX".to_string()].into_iter().collect())), vec!["VTSMX"]);
+}
+
+#[test]
+fn totals_are_shown_for_each_group() {
+    let fund_prices = fund_prices();
+    let tax_rates = TaxRates::flat(0.0);
+    let summary = account()
+        .minimum_cap_gains(&fund_prices, 3000.0, &tax_rates, date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    let mut s = String::new();
+    write_sell_summary(&mut s, summary, Some(3000.0), &tax_rates, &OutputOptions::default()).unwrap();
+    assert!(s.contains("\ntotal-stock: amount $1990.00, cap gains $990.00\n"));
+}
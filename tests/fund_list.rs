@@ -0,0 +1,27 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use capgains::{load_account, load_fund_prices, write_fund_list, InputOptions, OutputOptions, SellOptions, TaxRates};
+
+use common::{date, fixture};
+
+#[test]
+fn funds_are_listed_with_the_most_gains_first() {
+    let options = InputOptions::default();
+    let account = load_account(&fixture("account.csv"), &options).unwrap();
+    let fund_prices = load_fund_prices(&fixture("fund_prices.csv"), &options).unwrap();
+    let sell_records = account
+        .make_sell_records(&fund_prices, &TaxRates::flat(0.0), date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    let mut s = String::new();
+    write_fund_list(&mut s, &sell_records, &OutputOptions::default()).unwrap();
+    assert_eq!(
+        s,
+        "                       fund,     shares,   avg cost,      price,  cap gains\n\
+        \x20                     VTSAX,     25.000,      70.00,     100.00,     750.00\n\
+        \x20                     VBTLX,    100.000,      11.00,      10.50,     -50.00\n"
+    );
+}
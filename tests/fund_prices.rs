@@ -0,0 +1,162 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{
+    load_fund_prices, load_fund_prices_and_dates, load_fund_prices_on, Account, AccountError, Error, InputOptions, PriceTime,
+    SellOptions, TaxRates,
+};
+
+use common::{date, record, TempFile};
+
+#[test]
+fn zero_price_is_an_error_instead_of_a_panic() {
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VBTLX", 100.0, 20.0),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices.insert("VBTLX".to_string(), 0.0);
+    let options = SellOptions::default();
+    let result = account.minimum_cap_gains(&fund_prices, 500.0, &TaxRates::flat(0.0), date("2018-01-02"), &options);
+
+    assert_eq!(
+        result,
+        Err(AccountError::InvalidPrice {
+            fund: "VBTLX".to_string(),
+            price: 0.0
+        })
+    );
+}
+
+#[test]
+fn non_positive_prices_are_rejected_when_loaded() {
+    let file = TempFile::with_contents("prices.csv", "Fund,Share price\nVTSAX,100\nVBTLX,-1\n");
+    let result = load_fund_prices(file.path(), &InputOptions::default());
+
+    match result {
+        Err(Error::File(_, err)) => match *err {
+            Error::Account(AccountError::InvalidPrice { ref fund, price }) => {
+                assert_eq!((fund.as_str(), price), ("VBTLX", -1.0));
+            }
+            ref err => panic!("unexpected error: {}", err),
+        },
+        _ => panic!("expected an error"),
+    }
+}
+
+#[test]
+fn sale_dates_are_read_from_the_date_column() {
+    let file = TempFile::with_contents("dated-prices.csv", "Fund,Share price,Date\nVTSAX,100,2018-01-05\nVBTLX,10,\n");
+    let result = load_fund_prices_and_dates(file.path(), &InputOptions::default());
+
+    let (fund_prices, sale_dates) = result.unwrap();
+    assert_eq!(fund_prices.len(), 2);
+    assert_eq!(sale_dates.len(), 1);
+    assert_eq!(sale_dates["VTSAX"], date("2018-01-05"));
+}
+
+#[test]
+fn records_are_held_until_the_sale_date_of_their_fund() {
+    let account = Account::new(vec![
+        record("2017-01-03", "VTSAX", 10.0, 50.0),
+        record("2017-01-03", "VBTLX", 100.0, 10.0),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices.insert("VBTLX".to_string(), 11.0);
+    let mut options = SellOptions::default();
+    options.sale_dates.insert("VTSAX".to_string(), date("2018-01-05"));
+    let sell_records = account
+        .make_sell_records(&fund_prices, &TaxRates::flat(0.0), date("2018-01-02"), &options)
+        .unwrap();
+
+    assert_eq!(sell_records[0].fund, "VTSAX");
+    assert_eq!(sell_records[0].sale_date, date("2018-01-05"));
+    assert!(sell_records[0].is_long_term);
+    assert_eq!(sell_records[1].sale_date, date("2018-01-02"));
+    assert!(!sell_records[1].is_long_term);
+}
+
+fn load_price_history(price_date: &str) -> Result<HashMap<String, f64>, Error> {
+    let history = "Fund,Date,Share price\n\
+                   VTSAX,2018-01-02,100\n\
+                   VTSAX,2017-01-03,80\n\
+                   VTSAX,2018-01-05,105\n\
+                   VBTLX,2018-01-05,11\n";
+    let file = TempFile::with_contents(&format!("price-history-{}.csv", price_date), history);
+    let result = load_fund_prices_on(file.path(), date(price_date), &InputOptions::default());
+    result
+}
+
+#[test]
+fn price_history_gives_the_latest_price_on_or_before_the_date() {
+    let fund_prices = load_price_history("2018-01-02").unwrap();
+    assert_eq!(fund_prices["VTSAX"], 100.0);
+    // VBTLX has no price yet
+    assert_eq!(fund_prices.len(), 1);
+
+    assert_eq!(load_price_history("2018-01-04").unwrap()["VTSAX"], 100.0);
+    assert_eq!(load_price_history("2017-06-01").unwrap()["VTSAX"], 80.0);
+    assert_eq!(load_price_history("2018-01-05").unwrap().len(), 2);
+}
+
+#[test]
+fn fund_without_an_earlier_price_cannot_be_sold() {
+    let account = Account::new(vec![record("2015-01-02", "VBTLX", 100.0, 10.0)]);
+    let fund_prices = load_price_history("2018-01-02").unwrap();
+    let result = account.minimum_cap_gains(
+        &fund_prices,
+        500.0,
+        &TaxRates::flat(0.0),
+        date("2018-01-02"),
+        &SellOptions::default(),
+    );
+
+    assert_eq!(result, Err(AccountError::MissingPrice { fund: "VBTLX".to_string() }));
+}
+
+#[test]
+fn prices_can_be_separated_by_another_delimiter() {
+    let file = TempFile::with_contents("semicolon-prices.csv", "Fund;Share price\nVTSAX;100\nVBTLX;10.5\n");
+    let options = InputOptions {
+        delimiter: Some(b';'),
+        ..InputOptions::default()
+    };
+    let result = load_fund_prices(file.path(), &options);
+
+    let fund_prices = result.unwrap();
+    assert_eq!((fund_prices["VTSAX"], fund_prices["VBTLX"]), (100.0, 10.5));
+}
+
+#[test]
+fn price_of_the_selected_time_is_read() {
+    let contents = "Fund,Share price,Time\nVTSAX,100,09:30\nVTSAX,102,16:00\nVTSAX,101,12:00:30\nVBTLX,10,\n";
+    let file = TempFile::with_contents("intraday-prices.csv", contents);
+    let load = |price_time: PriceTime| {
+        let options = InputOptions {
+            price_time,
+            ..InputOptions::default()
+        };
+        let fund_prices = load_fund_prices(file.path(), &options).unwrap();
+        (fund_prices.get("VTSAX").cloned(), fund_prices.get("VBTLX").cloned())
+    };
+    let times = ["close", "open", "13:00", "12:00:30", "09:00"];
+    let prices: Vec<_> = times.iter().map(|time| load(time.parse().unwrap())).collect();
+
+    assert_eq!(PriceTime::default(), PriceTime::Close);
+    assert_eq!(
+        prices,
+        vec![
+            (Some(102.0), Some(10.0)),
+            (Some(100.0), Some(10.0)),
+            (Some(101.0), Some(10.0)),
+            (Some(101.0), Some(10.0)),
+            (None, Some(10.0)),
+        ]
+    );
+}
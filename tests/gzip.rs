@@ -0,0 +1,114 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+
+use capgains::gzip::Decoder;
+use capgains::{load_account, load_fund_prices, InputOptions};
+
+use common::TempFile;
+
+/// Compresses `contents` with the `gzip` command, which the tests use to make their input but
+/// which isn't needed to read it.
+fn compress(contents: &[u8]) -> Vec<u8> {
+    let mut gzip = Command::new("gzip").arg("-c").stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().unwrap();
+    gzip.stdin.take().unwrap().write_all(contents).unwrap();
+    let output = gzip.wait_with_output().unwrap();
+    assert!(output.status.success());
+    output.stdout
+}
+
+fn write_gzip(name: &str, contents: &str) -> TempFile {
+    TempFile::with_contents(name, compress(contents.as_bytes()))
+}
+
+fn decompress(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut contents = Vec::new();
+    Decoder::new(data).read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+/// "Fund,Share price\nVTSAX,100\n" in a single stored block, without compression.
+fn stored_gzip() -> Vec<u8> {
+    let contents = b"Fund,Share price\nVTSAX,100\n";
+    let mut data = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+    data.extend_from_slice(&[1, contents.len() as u8, 0, !(contents.len() as u8), 0xff]);
+    data.extend_from_slice(contents);
+    data.extend_from_slice(&[0xf9, 0xc8, 0xa8, 0x07, contents.len() as u8, 0, 0, 0]);
+    data
+}
+
+#[test]
+fn gz_files_are_decompressed() {
+    let account = write_gzip(
+        "account.csv.gz",
+        "Date,Fund,Transaction type,Shares transacted,Share price,Amount\n2015-01-02,VTSAX,Buy,10,50,500\n",
+    );
+    let prices = write_gzip("prices.csv.gz", "Fund,Share price\nVTSAX,100\n");
+
+    let options = InputOptions::default();
+    assert!(load_account(account.path(), &options).is_ok());
+    assert_eq!(load_fund_prices(prices.path(), &options).unwrap()["VTSAX"], 100.0);
+}
+
+#[test]
+fn gzip_option_decompresses_files_without_the_extension() {
+    let prices = write_gzip("prices-gzip.csv", "Fund,Share price\nVTSAX,100\n");
+
+    let options = InputOptions {
+        gzip: true,
+        ..InputOptions::default()
+    };
+    assert_eq!(load_fund_prices(prices.path(), &options).unwrap()["VTSAX"], 100.0);
+}
+
+#[test]
+fn file_that_is_not_gzip_is_an_error() {
+    let prices = TempFile::with_contents("plain.csv.gz", "Fund,Share price\nVTSAX,100\n");
+
+    assert!(load_fund_prices(prices.path(), &InputOptions::default()).is_err());
+}
+
+#[test]
+fn stored_blocks_are_decompressed() {
+    assert_eq!(decompress(&stored_gzip()).unwrap(), b"Fund,Share price\nVTSAX,100\n".to_vec());
+}
+
+#[test]
+fn contents_larger_than_the_window_are_decompressed() {
+    let contents: Vec<u8> = (0..20000)
+        .flat_map(|i| format!("2015-01-02,VTSAX,Buy,{},{}.{:02}\n", i % 97, i % 13 + 50, i % 100).into_bytes())
+        .collect();
+    assert!(contents.len() > 100 * 1024);
+
+    assert_eq!(decompress(&compress(&contents)).unwrap(), contents);
+}
+
+#[test]
+fn concatenated_members_are_decompressed_one_after_another() {
+    let mut data = compress(b"Fund,Share price\n");
+    data.extend(compress(b"VTSAX,100\n"));
+
+    assert_eq!(decompress(&data).unwrap(), b"Fund,Share price\nVTSAX,100\n".to_vec());
+}
+
+#[test]
+fn truncated_file_is_an_error() {
+    let data = compress(b"Fund,Share price\nVTSAX,100\n");
+
+    assert!(decompress(&data[..data.len() - 4]).is_err());
+    assert!(decompress(&[]).is_err());
+}
+
+#[test]
+fn corrupted_file_is_an_error() {
+    let mut data = stored_gzip();
+    // a byte of the contents no longer matches the checksum
+    data[15] = b'X';
+
+    let err = decompress(&data).unwrap_err();
+    assert_eq!(err.to_string(), "invalid compressed data--crc error");
+}
@@ -0,0 +1,125 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, AccountError, SellOptions, TaxRates, TransactionKind};
+
+use common::{date, record, transaction};
+
+#[test]
+fn sells_reduce_oldest_lots_first() {
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 10.0, 60.0),
+        record("2016-02-01", "VBTLX", 5.0, 10.0),
+        transaction("2017-01-03", "VTSAX", "Sell", -15.0, 70.0),
+    ]);
+    let holdings = account.holdings().unwrap();
+
+    assert_eq!(holdings.len(), 2);
+    assert_eq!(holdings[0].date, date("2016-01-04"));
+    assert_eq!(holdings[0].fund, "VTSAX");
+    assert_eq!(holdings[0].num_shares, 5.0);
+    assert_eq!(holdings[0].amount.to_dollars(), 300.0);
+    assert_eq!(holdings[1].fund, "VBTLX");
+    assert_eq!(holdings[1].num_shares, 5.0);
+}
+
+#[test]
+fn reinvestments_add_lots_and_dividends_are_ignored() {
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        transaction("2015-06-30", "VTSAX", "Dividend", 0.0, 55.0),
+        transaction("2015-06-30", "VTSAX", "Reinvestment", 0.5, 55.0),
+    ]);
+    let holdings = account.holdings().unwrap();
+
+    assert_eq!(holdings.len(), 2);
+    assert_eq!(holdings[1].num_shares, 0.5);
+}
+
+#[test]
+fn reinvested_lots_have_their_own_holding_period() {
+    let account = Account::new(vec![
+        record("2016-01-04", "VTSAX", 10.0, 50.0),
+        transaction("2017-06-30", "VTSAX", "Dividend Reinvest", 0.5, 60.0),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let sell_records = account
+        .make_sell_records(&fund_prices, &TaxRates::flat(0.0), date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    let kind = TransactionKind::from_transaction_type("dividend reinvestment");
+    assert_eq!(kind, Some(TransactionKind::Reinvestment));
+    assert_eq!(sell_records.len(), 2);
+    assert!(sell_records[0].is_long_term);
+    assert_eq!(sell_records[1].share_price_purchased, 60.0);
+    assert!(!sell_records[1].is_long_term);
+}
+
+#[test]
+fn unknown_transaction_type_is_an_error() {
+    let account = Account::new(vec![transaction("2015-01-02", "VTSAX", "Transfer", 10.0, 50.0)]);
+
+    assert!(account.holdings().is_err());
+}
+
+#[test]
+fn acquisition_of_negative_shares_is_a_short_position() {
+    let account = Account::new(vec![record("2015-01-02", "VTSAX", -10.0, 50.0)]);
+
+    assert_eq!(
+        account.holdings().unwrap_err(),
+        AccountError::ShortPosition {
+            fund: "VTSAX".to_string(),
+            date: date("2015-01-02"),
+            shares: 10.0,
+        }
+    );
+}
+
+#[test]
+fn selling_more_shares_than_held_is_a_short_position() {
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2015-01-02", "VBTLX", 10.0, 10.0),
+        transaction("2016-01-04", "VTSAX", "Sell", -15.0, 80.0),
+    ]);
+
+    assert_eq!(
+        account.holdings().unwrap_err(),
+        AccountError::ShortPosition {
+            fund: "VTSAX".to_string(),
+            date: date("2016-01-04"),
+            shares: 5.0,
+        }
+    );
+}
+
+#[test]
+fn short_position_names_the_sell_and_the_shares_missing() {
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        transaction("2016-01-04", "VTSAX", "Sell", 6.0, 80.0),
+        record("2016-06-01", "VTSAX", 0.5, 70.0),
+        transaction("2017-01-03", "VTSAX", "Sell", 6.0, 90.0),
+    ]);
+    let err = account.holdings().unwrap_err();
+
+    assert_eq!(
+        err,
+        AccountError::ShortPosition {
+            fund: "VTSAX".to_string(),
+            date: date("2017-01-03"),
+            shares: 1.5,
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "Short positions are not supported: fund VTSAX on 2017-01-03 is short 1.5 shares"
+    );
+}
@@ -0,0 +1,59 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use capgains::{load_account, load_fund_prices, Account, InputFormat, InputOptions};
+
+use common::TempFile;
+
+fn total_shares(account: &Account) -> f64 {
+    account.holdings().unwrap().iter().map(|r| r.num_shares).sum()
+}
+
+#[test]
+fn json_files_are_read_like_csv_files() {
+    let account = TempFile::with_contents(
+        "account.json",
+        r#"[
+            {"Date": "2015-01-02", "Fund": "VTSAX", "Transaction type": "Buy",
+             "Shares transacted": 10, "Share price": 50, "Amount": "$500.00", "Fee": null},
+            {"Date": "01/04/2016", "Fund": "VTSAX", "Transaction type": "Sell",
+             "Shares transacted": -4, "Share price": 80.5, "Amount": -322}
+        ]"#,
+    );
+    let prices = TempFile::with_contents("prices.json", r#"[{"Fund": "VTSAX", "Share price": 100.25}]"#);
+
+    let options = InputOptions::default();
+    assert_eq!(total_shares(&load_account(account.path(), &options).unwrap()), 6.0);
+    assert_eq!(load_fund_prices(prices.path(), &options).unwrap()["VTSAX"], 100.25);
+}
+
+#[test]
+fn input_format_overrides_the_extension() {
+    let prices = TempFile::with_contents("prices.txt", r#"[{"Fund": "VTSAX", "Share price": 100}]"#);
+    let options = InputOptions {
+        format: Some(InputFormat::Json),
+        ..InputOptions::default()
+    };
+    let result = load_fund_prices(prices.path(), &options);
+
+    assert_eq!(result.unwrap()["VTSAX"], 100.0);
+}
+
+#[test]
+fn malformed_json_is_an_error() {
+    let prices = TempFile::with_contents("malformed.json", r#"[{"Fund": "VTSAX", "Share price": 100}"#);
+    let result = load_fund_prices(prices.path(), &InputOptions::default());
+
+    let err = result.err().unwrap().to_string();
+    assert!(err.contains("expected ',' or ']'"), "{}", err);
+}
+
+#[test]
+fn nested_values_are_an_error() {
+    let prices = TempFile::with_contents("nested.json", r#"[{"Fund": "VTSAX", "Share price": [100]}]"#);
+    let result = load_fund_prices(prices.path(), &InputOptions::default());
+
+    assert!(result.is_err());
+}
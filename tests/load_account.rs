@@ -0,0 +1,141 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use capgains::money::Cents;
+use capgains::{load_account, load_accounts, Account, Error, InputOptions};
+
+use common::TempFile;
+
+const HEADER: &str = "Date,Fund,Transaction type,Shares transacted,Share price,Amount\n";
+
+fn write(name: &str, contents: &str) -> TempFile {
+    TempFile::with_contents(&format!("{}.csv", name), contents)
+}
+
+fn load(name: &str, contents: &str) -> Result<Account, Error> {
+    load_account(write(name, contents).path(), &InputOptions::default())
+}
+
+#[test]
+fn account_with_only_a_header_is_an_error() {
+    let err = load("header-only", HEADER).err().unwrap();
+
+    assert!(err.to_string().starts_with("No records loaded from "));
+}
+
+#[test]
+fn empty_account_file_is_an_error() {
+    assert!(load("empty", "").is_err());
+}
+
+#[test]
+fn malformed_last_record_is_an_error() {
+    let contents = format!("{}2015-01-02,VTSAX,Buy,10,50,500\n2016-01-04,VTSAX,Buy,ten,60,600\n", HEADER);
+    let err = load("malformed", &contents).err().unwrap();
+
+    assert!(err.to_string().contains("record 2: "), "{}", err);
+}
+
+fn total_shares(account: &Account) -> f64 {
+    account.holdings().unwrap().iter().map(|r| r.num_shares).sum()
+}
+
+#[test]
+fn records_of_several_files_are_merged() {
+    let first = write("2015", &format!("{}2015-01-02,VTSAX,Buy,10,50,500\n", HEADER));
+    let second = write("2016", &format!("{}2016-01-04,VTSAX,Sell,-4,80,-320\n", HEADER));
+    let result = load_accounts(&[first.path(), second.path()], &InputOptions::default());
+
+    // the sell in the second file reduces the lot bought in the first
+    assert_eq!(total_shares(&result.unwrap()), 6.0);
+}
+
+#[test]
+fn file_given_twice_is_read_once() {
+    let file = write("twice", &format!("{}2015-01-02,VTSAX,Buy,10,50,500\n", HEADER));
+    let result = load_accounts(&[file.path(), file.path()], &InputOptions::default());
+
+    assert_eq!(total_shares(&result.unwrap()), 10.0);
+}
+
+#[test]
+fn unknown_transaction_type_names_its_file() {
+    let first = write("known", &format!("{}2015-01-02,VTSAX,Buy,10,50,500\n", HEADER));
+    let second = write("unknown", &format!("{}2016-01-04,VTSAX,Gift,1,80,80\n", HEADER));
+    let result = load_accounts(&[first.path(), second.path()], &InputOptions::default());
+
+    let err = result.err().unwrap().to_string();
+    assert!(err.starts_with(second.path()), "{}", err);
+    assert!(err.contains("Unknown transaction type 'Gift'"), "{}", err);
+}
+
+#[test]
+fn records_keep_the_row_they_were_read_from() {
+    let contents = format!(
+        "{}2015-01-02,VTSAX,Buy,10,50,500\n2016-01-04,VBTLX,Buy,100,10,1000\n2017-01-03,VTSAX,Sell,-4,80,-320\n",
+        HEADER
+    );
+    let account = load("rows", &contents).unwrap();
+
+    // the partly sold lot keeps the row it was bought in
+    let rows: Vec<_> = account.holdings().unwrap().iter().map(|r| (r.fund.clone(), r.source_row)).collect();
+    assert_eq!(rows, vec![("VTSAX".to_string(), Some(1)), ("VBTLX".to_string(), Some(2))]);
+}
+
+#[test]
+fn negative_amounts_may_be_in_parentheses() {
+    let contents = format!(
+        "{}2015-01-02,VTSAX,Buy,1,100,($100.00)\n2015-01-02,VTSAX,Buy,1,100,-$100.00\n\
+         2015-01-02,VTSAX,Buy,1,100,$100.00\n2015-01-02,VTSAX,Buy,10,100,\"($1,000.00)\"\n",
+        HEADER
+    );
+    let account = load("parentheses", &contents).unwrap();
+
+    let amounts: Vec<_> = account.holdings().unwrap().iter().map(|r| r.amount.to_dollars()).collect();
+    assert_eq!(amounts, vec![-100.0, -100.0, 100.0, -1000.0]);
+}
+
+#[test]
+fn amounts_are_read_exactly_in_cents() {
+    let contents = format!("{}2015-01-02,VTSAX,Buy,1,1.015,\"$1.015\"\n", HEADER);
+    let account = load("exact-amounts", &contents).unwrap();
+
+    // read as an f64, the amount would be a little below the half cent and round down
+    assert_eq!(account.holdings().unwrap()[0].amount, Cents::new(102));
+}
+
+#[test]
+fn fields_can_be_separated_by_another_delimiter() {
+    let contents = "Date\tFund\tTransaction type\tShares transacted\tShare price\tAmount\n\
+                    2015-01-02\tVTSAX\tBuy\t10\t50\t\"$1,000.00\"\n";
+    let file = write("tabs", contents);
+    let options = InputOptions {
+        delimiter: Some(b'\t'),
+        ..InputOptions::default()
+    };
+    let result = load_account(file.path(), &options);
+    let commas = load_account(file.path(), &InputOptions::default());
+
+    let holdings = result.unwrap().holdings().unwrap();
+    assert_eq!((holdings[0].num_shares, holdings[0].amount.to_dollars()), (10.0, 1000.0));
+    assert!(commas.is_err());
+}
+
+#[test]
+fn numbers_that_are_not_finite_are_an_error() {
+    let rows = [
+        "2015-01-02,VTSAX,Buy,inf,50,500",
+        "2015-01-02,VTSAX,Buy,10,NaN,500",
+        "2015-01-02,VTSAX,Buy,10,50,-inf",
+        "2015-01-02,VTSAX,Buy,1e400,50,500",
+    ];
+    for (i, row) in rows.iter().enumerate() {
+        let contents = format!("{}2015-01-02,VTSAX,Buy,10,50,500\n{}\n", HEADER, row);
+        let err = load(&format!("not-finite-{}", i), &contents).err().unwrap();
+
+        assert!(err.to_string().contains("record 2: "), "{}: {}", row, err);
+        assert!(err.to_string().contains("expected a finite number"), "{}: {}", row, err);
+    }
+}
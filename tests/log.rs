@@ -0,0 +1,52 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::process::Command;
+
+use capgains::log::{self, Level};
+
+use common::fixture;
+
+fn stderr(args: &[&str], rust_log: Option<&str>) -> String {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_capgains"));
+    command.env_remove("RUST_LOG");
+    if let Some(rust_log) = rust_log {
+        command.env("RUST_LOG", rust_log);
+    }
+    let output = command
+        .args(["--account", &fixture("account.csv"), "--fund-prices", &fixture("fund_prices.csv")])
+        .args(["--as-of", "2018-01-02", "--target", "500", "--quiet"])
+        .args(args)
+        .output()
+        .unwrap();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn levels_are_read_from_rust_log() {
+    assert_eq!(log::level_from_env("debug"), Some(Level::Debug));
+    assert_eq!(log::level_from_env("other=trace,capgains=info"), Some(Level::Info));
+    assert_eq!(log::level_from_env("other=trace"), None);
+    assert_eq!(log::level_from_env("loud"), None);
+    assert!(Level::Error < Level::Trace);
+}
+
+#[test]
+fn nothing_is_logged_by_default() {
+    assert_eq!(stderr(&[], None), "");
+}
+
+#[test]
+fn debug_logs_the_records_read_and_the_selection() {
+    let logged = stderr(&["--log-level", "debug"], None);
+    assert!(logged.contains("[DEBUG capgains] read 4 records from "), "{}", logged);
+    assert!(logged.contains("[DEBUG capgains] sell record VBTLX 2017-06-01: "), "{}", logged);
+    assert!(logged.contains("[DEBUG capgains] selling VBTLX 2017-06-01: proceeds 1050.00 of target 500.00"));
+    assert!(!logged.contains("TRACE"));
+
+    assert_eq!(stderr(&[], Some("capgains=debug")), logged);
+    // the flag wins over RUST_LOG
+    assert_eq!(stderr(&["--log-level", "error"], Some("debug")), "");
+}
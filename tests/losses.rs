@@ -0,0 +1,90 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, SellOptions, TaxRates};
+
+use common::{date, record};
+
+// VBTLX is underwater: 100 shares bought at $20 now worth $10 each.
+// VTSAX has gains: 10 shares bought at $50 now worth $100 each.
+fn account() -> Account {
+    Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VBTLX", 100.0, 20.0),
+    ])
+}
+
+fn fund_prices() -> HashMap<String, f64> {
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices.insert("VBTLX".to_string(), 10.0);
+    fund_prices
+}
+
+#[test]
+fn losses_are_sold_first() {
+    let fund_prices = fund_prices();
+    let result = account()
+        .minimum_cap_gains(
+            &fund_prices,
+            500.0,
+            &TaxRates::flat(0.2),
+            date("2018-01-02"),
+            &SellOptions::default(),
+        )
+        .unwrap()
+        .records;
+
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].fund, "VBTLX");
+    assert_eq!(result[0].num_shares, 50.0);
+    assert_eq!(result[0].cap_gains.to_dollars(), -500.0);
+}
+
+#[test]
+fn losses_offset_gains_without_negative_taxes() {
+    // Selling all of VBTLX realizes a $1000 loss, which offsets the gains on the first VTSAX
+    // shares sold. The tax benefit of the loss is not counted toward the sell target, so the
+    // VTSAX shares must make up the full remaining $500.
+    let fund_prices = fund_prices();
+    let result = account()
+        .minimum_cap_gains(
+            &fund_prices,
+            1500.0,
+            &TaxRates::flat(0.2),
+            date("2018-01-02"),
+            &SellOptions::default(),
+        )
+        .unwrap()
+        .records;
+
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].fund, "VBTLX");
+    assert_eq!(result[0].num_shares, 100.0);
+    assert_eq!(result[1].fund, "VTSAX");
+    assert_eq!(result[1].num_shares, 5.0);
+
+    let amount: f64 = result.iter().map(|s| s.amount.to_dollars()).sum();
+    let cap_gains: f64 = result.iter().map(|s| s.cap_gains.to_dollars()).sum();
+    assert_eq!(amount, 1500.0);
+    assert_eq!(cap_gains, -750.0);
+}
+
+#[test]
+fn insufficient_funds_uses_gross_amount() {
+    let account = Account::new(vec![record("2016-01-04", "VBTLX", 100.0, 20.0)]);
+    let fund_prices = fund_prices();
+    let result = account.minimum_cap_gains(
+        &fund_prices,
+        1100.0,
+        &TaxRates::flat(0.2),
+        date("2018-01-02"),
+        &SellOptions::default(),
+    );
+
+    assert!(result.is_err());
+}
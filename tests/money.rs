@@ -0,0 +1,57 @@
+extern crate capgains;
+
+use capgains::money::Cents;
+
+#[test]
+fn dollars_are_rounded_to_cents_with_halves_to_even() {
+    assert_eq!(Cents::from_dollars(1234.56).cents(), 123456);
+    assert_eq!(Cents::from_dollars(0.125).cents(), 12);
+    assert_eq!(Cents::from_dollars(0.375).cents(), 38);
+    assert_eq!(Cents::from_dollars(-0.125).cents(), -12);
+}
+
+#[test]
+fn cents_are_added_exactly() {
+    let dimes: Cents = (0..10).map(|_| Cents::from_dollars(0.1)).sum();
+    assert_eq!(dimes, Cents::new(100));
+    assert_eq!(dimes.to_dollars(), 1.0);
+    assert_ne!((0..10).fold(0.0, |sum, _| sum + 0.1), 1.0);
+
+    assert_eq!(Cents::new(250) - Cents::new(300), -Cents::new(50));
+}
+
+#[test]
+fn amounts_are_parsed_exactly() {
+    assert_eq!("1234.56".parse(), Ok(Cents::new(123456)));
+    assert_eq!("-0.5".parse(), Ok(Cents::new(-50)));
+    assert_eq!(".05".parse(), Ok(Cents::new(5)));
+    assert_eq!("+7".parse(), Ok(Cents::new(700)));
+    assert!("1,234.56".parse::<Cents>().is_err());
+    // 1.015 is only approximately an f64, a little below the half cent
+    assert_eq!("1.015".parse(), Ok(Cents::new(102)));
+    assert_eq!(Cents::from_dollars(1.015), Cents::new(101));
+}
+
+#[test]
+fn digits_past_the_cents_are_rounded_with_halves_to_even() {
+    assert_eq!("0.125".parse(), Ok(Cents::new(12)));
+    assert_eq!("0.135".parse(), Ok(Cents::new(14)));
+    assert_eq!("0.1250001".parse(), Ok(Cents::new(13)));
+    assert_eq!("-0.375".parse(), Ok(Cents::new(-38)));
+}
+
+#[test]
+fn amounts_with_an_exponent_are_rounded_from_an_f64() {
+    assert_eq!("1.5e3".parse(), Ok(Cents::new(150000)));
+    assert!("inf".parse::<Cents>().is_err());
+    assert!("1e300".parse::<Cents>().is_err());
+    assert!("12.3.4".parse::<Cents>().is_err());
+    assert!("".parse::<Cents>().is_err());
+}
+
+#[test]
+fn cents_are_displayed_as_dollars() {
+    assert_eq!(Cents::new(-123450).to_string(), "-1234.50");
+    assert_eq!(Cents::new(5).to_string(), "0.05");
+    assert_eq!(format!("{:.0}", Cents::new(150)), "2");
+}
@@ -0,0 +1,51 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::fs;
+use std::process::{Command, Output};
+
+use common::{fixture, TempFile};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_capgains"))
+        .args(["--account", &fixture("account.csv"), "--fund-prices", &fixture("fund_prices.csv")])
+        .args(["--as-of", "2018-01-02", "--target", "500"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn sell_summary_is_written_to_the_file() {
+    let file = TempFile::with_contents("output.csv", "outdated contents that are longer than the summary\n".repeat(100));
+    let output = run(&["--format", "csv", "--output", file.path()]);
+    let written = fs::read_to_string(file.path()).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"");
+    assert_eq!(written, String::from_utf8(run(&["--format", "csv"]).stdout).unwrap());
+}
+
+#[test]
+fn progress_messages_stay_on_stdout() {
+    let file = TempFile::new("output.txt");
+    let output = run(&["--output", file.path()]);
+    let written = fs::read_to_string(file.path()).unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.starts_with("Reading account information from: "), "{}", stdout);
+    assert!(!stdout.contains("will result in"), "{}", stdout);
+    assert!(written.starts_with("Selling the following records on 2018-01-02:"), "{}", written);
+    assert!(written.contains("will result in"), "{}", written);
+}
+
+#[test]
+fn file_is_not_written_when_selling_fails() {
+    let file = TempFile::new("insufficient.txt");
+    let output = run(&["--target", "1000000", "--output", file.path()]);
+
+    assert_eq!(output.status.code(), Some(4));
+    assert!(fs::metadata(file.path()).is_err());
+}
@@ -0,0 +1,414 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{testutil, Account, AccountError, SellOptions, SellRecord, SellSummary, TargetKind, TaxRates};
+
+use common::{date, record};
+
+// Both lots are worth $100 a share. The 2016 lot has the smaller gains and is sold first.
+fn account() -> Account {
+    Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 10.0, 80.0),
+    ])
+}
+
+fn fund_prices() -> HashMap<String, f64> {
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices
+}
+
+fn sell<'a>(
+    account: &Account,
+    fund_prices: &'a HashMap<String, f64>,
+    sell_target: f64,
+    tax_rate: f64,
+    options: &SellOptions,
+) -> Vec<SellRecord<'a>> {
+    account
+        .minimum_cap_gains(fund_prices, sell_target, &TaxRates::flat(tax_rate), date("2018-01-02"), options)
+        .unwrap()
+        .records
+}
+
+fn shares(result: &[SellRecord]) -> Vec<f64> {
+    result.iter().map(|s| s.num_shares).collect()
+}
+
+fn total_amount(result: &[SellRecord]) -> f64 {
+    result.iter().map(|s| s.amount.to_dollars()).sum()
+}
+
+#[test]
+fn exact_target_within_a_lot() {
+    let fund_prices = fund_prices();
+    let result = sell(&account(), &fund_prices, 500.0, 0.0, &SellOptions::default());
+
+    assert_eq!(shares(&result), vec![5.0]);
+    assert_eq!(total_amount(&result), 500.0);
+    assert_eq!(result[0].cap_gains.to_dollars(), 100.0);
+}
+
+#[test]
+fn exact_target_at_a_lot_boundary() {
+    let fund_prices = fund_prices();
+    let result = sell(&account(), &fund_prices, 1000.0, 0.0, &SellOptions::default());
+
+    assert_eq!(shares(&result), vec![10.0]);
+    assert_eq!(result[0].date_purchased, date("2016-01-04"));
+    assert_eq!(total_amount(&result), 1000.0);
+}
+
+#[test]
+fn target_requiring_one_partial_lot() {
+    let fund_prices = fund_prices();
+    let result = sell(&account(), &fund_prices, 1250.0, 0.0, &SellOptions::default());
+
+    assert_eq!(shares(&result), vec![10.0, 3.0]);
+    assert_eq!(result[1].date_purchased, date("2015-01-02"));
+    assert_eq!(total_amount(&result), 1300.0);
+    assert_eq!(result.iter().map(|s| s.cap_gains.to_dollars()).sum::<f64>(), 350.0);
+}
+
+#[test]
+fn partial_lot_covers_taxes() {
+    // The 2016 lot nets $960 after taxes and each share of the 2015 lot nets $90, so a single
+    // share covers the remaining $40.
+    let fund_prices = fund_prices();
+    let result = sell(&account(), &fund_prices, 1000.0, 0.2, &SellOptions::default());
+
+    assert_eq!(shares(&result), vec![10.0, 1.0]);
+    assert_eq!(total_amount(&result), 1100.0);
+    assert_eq!(result.iter().map(|s| s.net_amount).sum::<f64>(), 1050.0);
+}
+
+#[test]
+fn target_equal_to_portfolio_value() {
+    let fund_prices = fund_prices();
+    let result = sell(&account(), &fund_prices, 2000.0, 0.0, &SellOptions::default());
+
+    assert_eq!(shares(&result), vec![10.0, 10.0]);
+    assert_eq!(total_amount(&result), 2000.0);
+}
+
+#[test]
+fn target_equal_to_portfolio_value_after_taxes() {
+    // $700 of gains taxed at 20% leave $1860 of the $2000
+    let fund_prices = fund_prices();
+    let options = SellOptions::default();
+    let summary = account()
+        .minimum_cap_gains(&fund_prices, 1860.0, &TaxRates::flat(0.2), date("2018-01-02"), &options)
+        .unwrap();
+
+    assert_eq!(shares(&summary.records), vec![10.0, 10.0]);
+    assert!(summary.remaining.is_empty());
+    assert_eq!(summary.net_amount, 1860.0);
+}
+
+#[test]
+fn gross_target_equal_to_portfolio_value() {
+    let options = SellOptions {
+        target_kind: TargetKind::Gross,
+        ..SellOptions::default()
+    };
+    let fund_prices = fund_prices();
+    let summary = account()
+        .minimum_cap_gains(&fund_prices, 2000.0, &TaxRates::flat(0.2), date("2018-01-02"), &options)
+        .unwrap();
+
+    assert_eq!(shares(&summary.records), vec![10.0, 10.0]);
+    assert!(summary.remaining.is_empty());
+    assert_eq!(summary.total_amount, 2000.0);
+}
+
+#[test]
+fn target_equal_to_the_total_of_many_records_sells_them_all() {
+    // the totals of the sale are summed in another order than the records are sold, which
+    // mustn't leave the target out of reach by rounding error
+    let (records, fund_prices) = testutil::generate(30, 1);
+    let account = Account::new(records);
+    let tax_rates = TaxRates {
+        long_term: 0.15,
+        ..TaxRates::flat(0.24)
+    };
+    let sale_date = date("2018-01-02");
+    let options = SellOptions::default();
+    let sell_records = account.make_sell_records(&fund_prices, &tax_rates, sale_date, &options).unwrap();
+    let total = SellSummary::new(sell_records, &tax_rates).net_amount;
+    let summary = account.minimum_cap_gains(&fund_prices, total, &tax_rates, sale_date, &options).unwrap();
+
+    assert_eq!(summary.records.len(), 30);
+    assert!(summary.remaining.is_empty());
+}
+
+#[test]
+fn target_above_portfolio_value_after_taxes_is_insufficient_funds() {
+    let fund_prices = fund_prices();
+    let result = account().minimum_cap_gains(
+        &fund_prices,
+        2000.0,
+        &TaxRates::flat(0.2),
+        date("2018-01-02"),
+        &SellOptions::default(),
+    );
+
+    assert_eq!(
+        result,
+        Err(AccountError::InsufficientFunds {
+            available: 1860.0,
+            target: 2000.0
+        })
+    );
+}
+
+#[test]
+fn target_above_portfolio_value_is_insufficient_funds() {
+    let fund_prices = fund_prices();
+    let result = account().minimum_cap_gains(
+        &fund_prices,
+        2500.0,
+        &TaxRates::flat(0.0),
+        date("2018-01-02"),
+        &SellOptions::default(),
+    );
+
+    assert_eq!(
+        result,
+        Err(AccountError::InsufficientFunds {
+            available: 2000.0,
+            target: 2500.0
+        })
+    );
+}
+
+#[test]
+fn fractional_lot_is_sold_in_whole_shares() {
+    let account = Account::new(vec![record("2016-01-04", "VTSAX", 10.5, 80.0)]);
+    let fund_prices = fund_prices();
+    let result = sell(&account, &fund_prices, 1000.0, 0.0, &SellOptions::default());
+
+    assert_eq!(shares(&result), vec![10.0]);
+    assert_eq!(total_amount(&result), 1000.0);
+}
+
+#[test]
+fn fractional_shares_hit_the_target() {
+    let options = SellOptions {
+        fractional: true,
+        ..SellOptions::default()
+    };
+    let fund_prices = fund_prices();
+    let result = sell(&account(), &fund_prices, 1234.5, 0.0, &options);
+
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[1].num_shares, 2.345);
+    assert!((total_amount(&result) - 1234.5).abs() < 1e-9);
+}
+
+#[test]
+fn fractional_shares_round_up_to_a_thousandth() {
+    let options = SellOptions {
+        fractional: true,
+        ..SellOptions::default()
+    };
+    let fund_prices = fund_prices();
+    let result = sell(&account(), &fund_prices, 500.05, 0.0, &options);
+
+    assert_eq!(shares(&result), vec![5.001]);
+}
+
+#[test]
+fn target_shares_of_a_fund() {
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 10.0, 80.0),
+        record("2016-01-04", "VBTLX", 100.0, 20.0),
+    ]);
+    let mut fund_prices = fund_prices();
+    fund_prices.insert("VBTLX".to_string(), 10.0);
+    let result = account
+        .minimum_cap_gains_shares(
+            &fund_prices,
+            "VTSAX",
+            12.5,
+            &TaxRates::flat(0.0),
+            date("2018-01-02"),
+            &SellOptions::default(),
+        )
+        .unwrap()
+        .records;
+
+    assert!(result.iter().all(|s| s.fund == "VTSAX"));
+    assert_eq!(shares(&result), vec![10.0, 2.5]);
+    assert_eq!(total_amount(&result), 1250.0);
+}
+
+#[test]
+fn min_shares_skips_small_records_and_raises_partial_sales() {
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 10.0, 80.0),
+        // the lowest ratio, but too small to sell
+        record("2017-06-01", "VTSAX", 0.5, 95.0),
+    ]);
+    let options = SellOptions {
+        min_shares: 4.0,
+        ..SellOptions::default()
+    };
+    let fund_prices = fund_prices();
+    let result = sell(&account, &fund_prices, 1100.0, 0.0, &options);
+
+    assert_eq!(shares(&result), vec![10.0, 4.0]);
+    assert_eq!(result[1].date_purchased, date("2015-01-02"));
+    assert_eq!(total_amount(&result), 1400.0);
+}
+
+#[test]
+fn remaining_records_include_the_rest_of_a_partial_lot() {
+    let fund_prices = fund_prices();
+    let options = SellOptions::default();
+    let summary = account()
+        .minimum_cap_gains(&fund_prices, 1250.0, &TaxRates::flat(0.0), date("2018-01-02"), &options)
+        .unwrap();
+
+    assert_eq!(shares(&summary.records), vec![10.0, 3.0]);
+    assert_eq!(shares(&summary.remaining), vec![7.0]);
+    assert_eq!(summary.remaining[0].date_purchased, date("2015-01-02"));
+    assert_eq!(summary.remaining[0].amount.to_dollars(), 700.0);
+    assert_eq!(summary.remaining[0].cap_gains.to_dollars(), 350.0);
+}
+
+#[test]
+fn remaining_records_include_lots_not_considered_for_the_sale() {
+    let fund_prices = fund_prices();
+    let options = SellOptions::default();
+    let summary = account()
+        .minimum_cap_gains(&fund_prices, 1000.0, &TaxRates::flat(0.0), date("2018-01-02"), &options)
+        .unwrap();
+
+    assert_eq!(shares(&summary.records), vec![10.0]);
+    assert_eq!(shares(&summary.remaining), vec![10.0]);
+    assert_eq!(summary.remaining[0].date_purchased, date("2015-01-02"));
+}
+
+#[test]
+fn max_lots_passes_over_records_that_leave_the_target_out_of_reach() {
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 10.0, 80.0),
+        // the lowest ratios, but too small to reach the target with one other record
+        record("2017-06-01", "VTSAX", 2.0, 95.0),
+        record("2017-09-01", "VTSAX", 1.0, 90.0),
+    ]);
+    let options = SellOptions {
+        max_lots: Some(2),
+        ..SellOptions::default()
+    };
+    let fund_prices = fund_prices();
+    let result = sell(&account, &fund_prices, 1500.0, 0.0, &options);
+
+    assert_eq!(shares(&result), vec![10.0, 5.0]);
+    assert_eq!(result[0].date_purchased, date("2016-01-04"));
+    assert_eq!(result[1].date_purchased, date("2015-01-02"));
+}
+
+#[test]
+fn max_lots_keeps_the_greedy_records_when_they_are_few_enough() {
+    let options = SellOptions {
+        max_lots: Some(2),
+        ..SellOptions::default()
+    };
+    let fund_prices = fund_prices();
+
+    assert_eq!(
+        sell(&account(), &fund_prices, 1250.0, 0.0, &options),
+        sell(&account(), &fund_prices, 1250.0, 0.0, &SellOptions::default())
+    );
+}
+
+#[test]
+fn target_out_of_reach_of_max_lots_is_an_error() {
+    let options = SellOptions {
+        max_lots: Some(1),
+        ..SellOptions::default()
+    };
+    let fund_prices = fund_prices();
+    let result =
+        account().minimum_cap_gains(&fund_prices, 1500.0, &TaxRates::flat(0.0), date("2018-01-02"), &options);
+
+    assert_eq!(
+        result,
+        Err(AccountError::InsufficientFundsInLots {
+            max_lots: 1,
+            available: 1000.0,
+            target: 1500.0
+        })
+    );
+}
+
+#[test]
+fn no_sell_after_skips_later_records() {
+    let options = SellOptions {
+        no_sell_after: Some(date("2015-12-31")),
+        ..SellOptions::default()
+    };
+    let fund_prices = fund_prices();
+    let summary = account()
+        .minimum_cap_gains(&fund_prices, 500.0, &TaxRates::flat(0.0), date("2018-01-02"), &options)
+        .unwrap();
+
+    assert_eq!(shares(&summary.records), vec![5.0]);
+    assert_eq!(summary.records[0].date_purchased, date("2015-01-02"));
+    assert_eq!(shares(&summary.remaining), vec![10.0, 5.0]);
+}
+
+#[test]
+fn no_sell_after_can_leave_insufficient_funds() {
+    let options = SellOptions {
+        no_sell_after: Some(date("2015-12-31")),
+        ..SellOptions::default()
+    };
+    let fund_prices = fund_prices();
+    let result =
+        account().minimum_cap_gains(&fund_prices, 1500.0, &TaxRates::flat(0.0), date("2018-01-02"), &options);
+
+    assert_eq!(
+        result,
+        Err(AccountError::InsufficientFunds {
+            available: 1000.0,
+            target: 1500.0
+        })
+    );
+}
+
+#[test]
+fn whole_lots_stops_short_of_the_target_when_closer() {
+    let options = SellOptions {
+        whole_lots: true,
+        ..SellOptions::default()
+    };
+    let fund_prices = fund_prices();
+    // selling the second lot whole would overshoot by $800
+    let summary = account()
+        .minimum_cap_gains(&fund_prices, 1200.0, &TaxRates::flat(0.0), date("2018-01-02"), &options)
+        .unwrap();
+    assert_eq!(shares(&summary.records), vec![10.0]);
+    assert_eq!(summary.total_amount, 1000.0);
+    assert_eq!(summary.remaining.len(), 1);
+}
+
+#[test]
+fn whole_lots_overshoots_the_target_when_closer() {
+    let options = SellOptions {
+        whole_lots: true,
+        ..SellOptions::default()
+    };
+    assert_eq!(shares(&sell(&account(), &fund_prices(), 1600.0, 0.0, &options)), vec![10.0, 10.0]);
+    // on a tie the target is reached
+    assert_eq!(shares(&sell(&account(), &fund_prices(), 1500.0, 0.0, &options)), vec![10.0, 10.0]);
+}
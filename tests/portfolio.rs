@@ -0,0 +1,37 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, Portfolio, SellOptions, TaxRates};
+
+use common::{date, record};
+
+#[test]
+fn tax_free_accounts_are_sold_first_without_taxes() {
+    let taxable = Account::new(vec![record("2016-01-04", "VBTLX", 100.0, 9.0)]).with_name("brokerage");
+    let ira = Account::new(vec![record("2015-01-02", "VTSAX", 10.0, 50.0)])
+        .with_name("ira")
+        .with_taxable(false);
+    let portfolio = Portfolio::new(vec![taxable, ira]);
+
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices.insert("VBTLX".to_string(), 10.0);
+    let tax_rates = TaxRates::flat(0.2);
+    let result = portfolio
+        .minimum_cap_gains(&fund_prices, 1098.0, &tax_rates, date("2018-01-02"), &SellOptions::default())
+        .unwrap()
+        .records;
+
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].account, Some("ira".to_string()));
+    assert_eq!(result[0].num_shares, 10.0);
+    assert_eq!(tax_rates.taxes(&result[0]), 0.0);
+    assert_eq!(result[0].net_amount, 1000.0);
+    assert_eq!(result[1].account, Some("brokerage".to_string()));
+    assert_eq!(result[1].num_shares, 10.0);
+    assert_eq!(result[1].net_amount, 98.0);
+}
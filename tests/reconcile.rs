@@ -0,0 +1,50 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{load_balances, reconcile, Account, BalanceCheck, BalanceKind, InputOptions, SellOptions, TaxRates};
+
+use common::{date, record, TempFile};
+
+#[test]
+fn stated_balances_are_compared_with_the_records() {
+    let file = TempFile::with_contents("balances.csv", "Fund,Shares,Value\nVTSAX,20,\nVBTLX,99,\"$1,000.00\"\nVTIAX,,50\n");
+    let balances = load_balances(file.path(), &InputOptions::default());
+
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 10.0, 80.0),
+        record("2016-01-04", "VBTLX", 100.0, 9.0),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices.insert("VBTLX".to_string(), 10.0);
+    let sell_records = account
+        .make_sell_records(&fund_prices, &TaxRates::flat(0.0), date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    let check = |fund: &str, kind, stated, computed| BalanceCheck {
+        fund: fund.to_string(),
+        kind,
+        stated,
+        computed,
+    };
+    let checks = reconcile(&sell_records, &balances.unwrap());
+    assert_eq!(
+        checks,
+        vec![
+            check("VTSAX", BalanceKind::Shares, 20.0, 20.0),
+            check("VBTLX", BalanceKind::Shares, 99.0, 100.0),
+            check("VBTLX", BalanceKind::Value, 1000.0, 1000.0),
+            // no records of the fund are held
+            check("VTIAX", BalanceKind::Value, 50.0, 0.0),
+        ]
+    );
+    let passed: Vec<_> = checks.iter().map(|c| c.matches(0.01)).collect();
+    assert_eq!(passed, vec![true, false, true, false]);
+    assert_eq!(checks[1].delta(), 1.0);
+    assert!(checks[1].matches(1.0));
+}
@@ -0,0 +1,100 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::money::Cents;
+use capgains::{Account, SellOptions, SellSummary, TaxRates, PRECISION_TOLERANCE};
+
+use common::{date, record};
+
+#[test]
+fn cap_gains_are_rounded_to_cents_with_halves_to_even() {
+    let account = Account::new(vec![record("2015-01-02", "VTSAX", 1.0, 99.875)]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let sell_records = account
+        .make_sell_records(&fund_prices, &TaxRates::flat(0.0), date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    // the gains of 0.125 are rounded down to the even cent
+    assert_eq!(sell_records[0].cap_gains, Cents::new(12));
+}
+
+#[test]
+fn total_cap_gains_are_the_sum_of_the_rounded_cap_gains() {
+    // each record has capital gains of a third of a dollar
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 1.0, 300.0 - 1.0 / 3.0),
+        record("2015-02-02", "VTSAX", 1.0, 300.0 - 1.0 / 3.0),
+        record("2015-03-02", "VTSAX", 1.0, 300.0 - 1.0 / 3.0),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 300.0);
+    let options = SellOptions::default();
+    let summary = account
+        .minimum_cap_gains(&fund_prices, 900.0, &TaxRates::flat(0.0), date("2018-01-02"), &options)
+        .unwrap();
+
+    let shown: Vec<_> = summary.records.iter().map(|s| s.cap_gains.to_string()).collect();
+    assert_eq!(shown, vec!["0.33", "0.33", "0.33"]);
+    assert_eq!(format!("{:.2}", summary.total_cap_gains), "0.99");
+    assert_eq!(format!("{:.2}", summary.long_term_cap_gains), "0.99");
+}
+
+#[test]
+fn totals_are_summed_without_drift() {
+    // at $1e16 a dollar is below the precision of an f64, so summing naively drops every dollar
+    let mut records = vec![record("2015-01-02", "VTSAX", 1e14, 50.0)];
+    records.extend((0..10).map(|_| record("2015-01-02", "VTSAX", 0.01, 50.0)));
+    let account = Account::new(records);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let tax_rates = TaxRates::flat(0.0);
+    let sell_records = account
+        .make_sell_records(&fund_prices, &tax_rates, date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+    let summary = SellSummary::new(sell_records, &tax_rates);
+
+    assert_eq!(summary.total_amount, 1e16 + 10.0);
+    assert!(summary.precision_drift() > PRECISION_TOLERANCE);
+}
+
+#[test]
+fn small_sales_have_no_drift() {
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 10.0, 80.0),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let summary = account
+        .minimum_cap_gains(&fund_prices, 1250.0, &TaxRates::flat(0.2), date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    assert_eq!(summary.precision_drift(), 0.0);
+}
+
+#[test]
+fn total_amount_is_the_sum_of_the_rounded_amounts() {
+    // each record is worth $1.005, shown as $1.00, and $3.015 in total
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 1.0, 1.0),
+        record("2015-02-02", "VTSAX", 1.0, 1.0),
+        record("2015-03-02", "VTSAX", 1.0, 1.0),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 1.005);
+    let tax_rates = TaxRates::flat(0.0);
+    let sell_records = account
+        .make_sell_records(&fund_prices, &tax_rates, date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+    let summary = SellSummary::new(sell_records, &tax_rates);
+
+    let shown: Vec<_> = summary.records.iter().map(|s| s.amount.to_string()).collect();
+    assert_eq!(shown, vec!["1.00", "1.00", "1.00"]);
+    assert_eq!(summary.total_amount, 3.0);
+    assert_eq!(summary.net_amount, 3.0);
+}
@@ -0,0 +1,64 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, SellOptions, TaxRates};
+
+use common::{date, record};
+
+#[test]
+fn holding_period_is_counted_up_to_the_sale_date() {
+    let account = Account::new(vec![record("2017-01-02", "VTSAX", 10.0, 50.0)]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let tax_rates = TaxRates::flat(0.0);
+    let options = SellOptions::default();
+
+    let result = account.make_sell_records(&fund_prices, &tax_rates, date("2018-01-02"), &options).unwrap();
+    assert_eq!(result[0].sale_date, date("2018-01-02"));
+    assert_eq!(result[0].holding_days, 365);
+    assert!(!result[0].is_long_term);
+
+    let result = account.make_sell_records(&fund_prices, &tax_rates, date("2018-01-03"), &options).unwrap();
+    assert_eq!(result[0].sale_date, date("2018-01-03"));
+    assert!(result[0].is_long_term);
+}
+
+#[test]
+fn long_term_days_sets_the_holding_period() {
+    let account = Account::new(vec![record("2017-07-03", "VTSAX", 10.0, 50.0)]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let tax_rates = TaxRates::flat(0.0);
+    let options = SellOptions {
+        long_term_days: Some(180),
+        ..SellOptions::default()
+    };
+
+    let result = account.make_sell_records(&fund_prices, &tax_rates, date("2017-12-30"), &options).unwrap();
+    assert_eq!(result[0].holding_days, 180);
+    assert!(!result[0].is_long_term);
+
+    let result = account.make_sell_records(&fund_prices, &tax_rates, date("2017-12-31"), &options).unwrap();
+    assert!(result[0].is_long_term);
+}
+
+#[test]
+fn returns_are_annualized_over_the_holding_period() {
+    let account = Account::new(vec![
+        record("2016-01-02", "VTSAX", 10.0, 25.0),
+        record("2017-01-02", "VTSAX", 10.0, 50.0),
+        record("2018-01-02", "VTSAX", 10.0, 90.0),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let options = SellOptions::default();
+    let result = account.make_sell_records(&fund_prices, &TaxRates::flat(0.0), date("2018-01-02"), &options).unwrap();
+    let annualized: Vec<_> = result.iter().map(|s| s.annualized_return().map(|r| (r * 1e4).round() / 1e4)).collect();
+
+    // quadrupled over 731 days, doubled over 365 days and bought on the sale date
+    assert_eq!(annualized, vec![Some(0.9991), Some(1.0009), None]);
+}
@@ -0,0 +1,60 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, Fifo, SellOptions, SellRecord, TaxRates};
+
+use common::{date, record};
+
+fn account() -> Account {
+    Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 5.0, 80.0),
+        record("2017-01-03", "VBTLX", 100.0, 9.0),
+    ])
+}
+
+fn fund_prices() -> HashMap<String, f64> {
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices.insert("VBTLX".to_string(), 10.0);
+    fund_prices
+}
+
+#[test]
+fn built_in_methods_are_strategies() {
+    let fund_prices = fund_prices();
+    let tax_rates = TaxRates::flat(0.2);
+    let sale_date = date("2018-01-02");
+    let options = SellOptions::default();
+
+    assert_eq!(
+        account().select_with(&fund_prices, 1200.0, &tax_rates, sale_date, &options, &Fifo),
+        account().fifo_sell(&fund_prices, 1200.0, &tax_rates, sale_date, &options)
+    );
+}
+
+#[test]
+fn a_closure_is_a_strategy() {
+    // sell the largest records first
+    let largest_first = |sell_records: &mut [SellRecord], _: &TaxRates| {
+        sell_records.sort_by(|a, b| b.amount.partial_cmp(&a.amount).unwrap());
+    };
+    let fund_prices = fund_prices();
+    let summary = account()
+        .select_with(
+            &fund_prices,
+            1200.0,
+            &TaxRates::flat(0.0),
+            date("2018-01-02"),
+            &SellOptions::default(),
+            &largest_first,
+        )
+        .unwrap();
+
+    let lots: Vec<_> = summary.records.iter().map(|s| (s.date_purchased, s.num_shares)).collect();
+    assert_eq!(lots, vec![(date("2015-01-02"), 10.0), (date("2017-01-03"), 20.0)]);
+}
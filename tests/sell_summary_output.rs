@@ -0,0 +1,94 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use capgains::{
+    load_account, load_fund_prices, write_sell_summary, InputOptions, OutputOptions, SellOptions, TaxRates,
+};
+
+use common::{assert_golden, date, fixture};
+
+fn sell_summary(sell_target: f64, tax_rates: &TaxRates) -> String {
+    sell_summary_with(sell_target, tax_rates, &OutputOptions::default())
+}
+
+fn sell_summary_with(sell_target: f64, tax_rates: &TaxRates, output_options: &OutputOptions) -> String {
+    let options = InputOptions::default();
+    let account = load_account(&fixture("account.csv"), &options).unwrap();
+    let fund_prices = load_fund_prices(&fixture("fund_prices.csv"), &options).unwrap();
+    let summary = account
+        .minimum_cap_gains(&fund_prices, sell_target, tax_rates, date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    let mut s = String::new();
+    write_sell_summary(&mut s, summary, Some(sell_target), tax_rates, output_options).unwrap();
+    s
+}
+
+#[test]
+fn sell_summary_without_taxes() {
+    assert_golden("sell_summary_without_taxes.txt", &sell_summary(2000.0, &TaxRates::flat(0.0)));
+}
+
+#[test]
+fn sell_summary_with_taxes() {
+    let tax_rates = TaxRates {
+        long_term: 0.15,
+        ..TaxRates::flat(0.25)
+    };
+    assert_golden("sell_summary_with_taxes.txt", &sell_summary(2000.0, &tax_rates));
+}
+
+#[test]
+fn sell_summary_by_fund() {
+    let options = OutputOptions {
+        by_fund: true,
+        ..OutputOptions::default()
+    };
+    assert_golden("sell_summary_by_fund.txt", &sell_summary_with(2500.0, &TaxRates::flat(0.0), &options));
+}
+
+#[test]
+fn net_loss_is_noted() {
+    // the VBTLX record is held at a loss and sold first
+    let s = sell_summary(500.0, &TaxRates::flat(0.0));
+    assert!(s.contains("\nnote: this sale realizes a net capital loss of $24.00.\n"));
+
+    let quiet = OutputOptions {
+        quiet: true,
+        ..OutputOptions::default()
+    };
+    assert!(!sell_summary_with(500.0, &TaxRates::flat(0.0), &quiet).contains("note:"));
+    assert!(!sell_summary(2000.0, &TaxRates::flat(0.0)).contains("note:"));
+}
+
+#[test]
+fn rows_are_shown() {
+    let options = OutputOptions {
+        show_rows: true,
+        ..OutputOptions::default()
+    };
+    let s = sell_summary_with(2000.0, &TaxRates::flat(0.0), &options);
+    let lines: Vec<_> = s.lines().skip(1).take(4).map(|l| &l[..21]).collect();
+    assert_eq!(lines, vec!["     row,        date", "       4,  2017-09-01", "       3,  2017-06-01", "       2,  2016-01-04"]);
+}
+
+#[test]
+fn annualized_returns_are_shown() {
+    let options = OutputOptions {
+        show_annualized: true,
+        ..OutputOptions::default()
+    };
+    let s = sell_summary_with(2000.0, &TaxRates::flat(0.0), &options);
+    let lines: Vec<_> = s.lines().skip(1).take(4).map(|l| &l[65..]).collect();
+    assert_eq!(
+        lines,
+        vec![
+            "  days,  term, annualized,     shares",
+            "   123, short,     36.73%,          5 [whole]",
+            "   215, short,     -7.60%,        100 [whole]",
+            "   729,  long,     11.83%,          5 [whole]",
+        ]
+    );
+}
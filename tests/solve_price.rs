@@ -0,0 +1,82 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, AccountError, FundFilter, SellOptions, SellSummary, TargetKind, TaxRates};
+
+use common::{date, record};
+
+fn account() -> Account {
+    Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 10.0, 80.0),
+        record("2016-01-04", "VBTLX", 100.0, 10.0),
+    ])
+}
+
+#[test]
+fn price_reaches_the_net_target() {
+    let tax_rates = TaxRates::flat(0.2);
+    let sale_date = date("2018-01-02");
+    let options = SellOptions::default();
+    // 20 shares with a basis of $1300 net 16p + 260 at a price of p
+    let share_price = account().solve_price("VTSAX", &[], 2000.0, &tax_rates, sale_date, &options).unwrap();
+    assert_eq!(share_price, 108.75);
+
+    // selling at the price reaches the target, and a cent less does not
+    let net = |share_price: f64| {
+        let mut fund_prices = HashMap::new();
+        fund_prices.insert("VTSAX".to_string(), share_price);
+        let options = SellOptions {
+            funds: FundFilter::Only(vec!["VTSAX".to_string()].into_iter().collect()),
+            ..SellOptions::default()
+        };
+        let sell_records = account().make_sell_records(&fund_prices, &tax_rates, sale_date, &options).unwrap();
+        SellSummary::new(sell_records, &tax_rates).net_amount
+    };
+    assert!(net(share_price) >= 2000.0);
+    assert!(net(share_price - 0.01) < 2000.0);
+}
+
+#[test]
+fn price_of_chosen_lots() {
+    let options = SellOptions {
+        target_kind: TargetKind::Gross,
+        ..SellOptions::default()
+    };
+    let share_price = account()
+        .solve_price("VTSAX", &[date("2016-01-04")], 1000.0, &TaxRates::flat(0.2), date("2018-01-02"), &options)
+        .unwrap();
+    assert_eq!(share_price, 100.0);
+}
+
+#[test]
+fn lot_that_is_not_held_is_an_error() {
+    let result = account().solve_price(
+        "VTSAX",
+        &[date("2017-01-03")],
+        1000.0,
+        &TaxRates::flat(0.0),
+        date("2018-01-02"),
+        &SellOptions::default(),
+    );
+    assert_eq!(
+        result,
+        Err(AccountError::RecordNotFound {
+            fund: "VTSAX".to_string(),
+            date: date("2017-01-03"),
+        })
+    );
+}
+
+#[test]
+fn price_of_a_very_large_target_is_found() {
+    let tax_rates = TaxRates::flat(0.0);
+    let options = SellOptions::default();
+    // 20 shares of VTSAX sell for 1e12 at 5e10, where floats are more than 1e-6 apart
+    let share_price = account().solve_price("VTSAX", &[], 1e12, &tax_rates, date("2018-01-02"), &options).unwrap();
+    assert_eq!(share_price, 5e10);
+}
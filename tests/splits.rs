@@ -0,0 +1,49 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use capgains::{load_splits, Account, InputOptions, Split};
+
+use common::{date, record, transaction, TempFile};
+
+fn split(fund: &str, date_str: &str, ratio: f64) -> Split {
+    Split {
+        fund: fund.to_string(),
+        date: date(date_str),
+        ratio,
+    }
+}
+
+#[test]
+fn lots_are_adjusted_for_each_later_split() {
+    let account = Account::new(vec![
+        record("2010-01-04", "VTSAX", 10.0, 120.0),
+        transaction("2011-01-03", "VTSAX", "Sell", -5.0, 130.0),
+        record("2013-01-02", "VTSAX", 10.0, 60.0),
+        record("2013-01-02", "VBTLX", 10.0, 10.0),
+    ])
+    .with_splits(&[split("VTSAX", "2012-01-03", 2.0), split("VTSAX", "2015-01-02", 3.0)]);
+
+    let holdings = account.holdings().unwrap();
+    let lots: Vec<_> = holdings.iter().map(|r| (r.fund.as_str(), r.num_shares, r.share_price)).collect();
+    assert_eq!(
+        lots,
+        vec![
+            // 10 shares before both splits, of which 5 were sold before the first split
+            ("VTSAX", 30.0, 20.0),
+            ("VTSAX", 30.0, 20.0),
+            ("VBTLX", 10.0, 10.0),
+        ]
+    );
+}
+
+#[test]
+fn split_ratios_are_read() {
+    let contents = "Fund,Date,Ratio\nVTSAX,2012-01-03,2:1\nVBTLX,2014-06-02,1:10\nVTIAX,2015-01-02,3\n";
+    let file = TempFile::with_contents("splits.csv", contents);
+    let result = load_splits(file.path(), &InputOptions::default());
+
+    let ratios: Vec<_> = result.unwrap().iter().map(|s| s.ratio).collect();
+    assert_eq!(ratios, vec![2.0, 0.1, 3.0]);
+}
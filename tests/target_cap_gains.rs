@@ -0,0 +1,49 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, SellOptions, TaxRates};
+
+use common::{date, record};
+
+// The 2015 lot gains $50 a share, the 2016 lot $20 a share and the 2017 lot loses $20 a share.
+fn account() -> Account {
+    Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 10.0, 80.0),
+        record("2017-01-03", "VTSAX", 10.0, 120.0),
+    ])
+}
+
+fn sell(target_cap_gains: f64) -> Result<Vec<(chrono::NaiveDate, f64)>, String> {
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    account()
+        .target_cap_gains(
+            &fund_prices,
+            target_cap_gains,
+            &TaxRates::flat(0.0),
+            date("2018-01-02"),
+            &SellOptions::default(),
+        )
+        .map(|summary| summary.records.iter().map(|s| (s.date_purchased, s.num_shares)).collect())
+        .map_err(|err| err.to_string())
+}
+
+#[test]
+fn gains_are_realized_from_the_highest_ratio_first() {
+    assert_eq!(sell(500.0), Ok(vec![(date("2015-01-02"), 10.0)]));
+    // the 2016 lot needs 3 whole shares for the remaining $50
+    assert_eq!(sell(550.0), Ok(vec![(date("2015-01-02"), 10.0), (date("2016-01-04"), 3.0)]));
+    assert_eq!(sell(0.0), Ok(vec![]));
+}
+
+#[test]
+fn negative_target_realizes_losses() {
+    assert_eq!(sell(-100.0), Ok(vec![(date("2017-01-03"), 5.0)]));
+    assert!(sell(-300.0).is_err());
+    assert!(sell(800.0).is_err());
+}
@@ -0,0 +1,72 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, AccountError, SellOptions, SellSummary, TargetKind, TaxRates};
+
+use common::{date, record};
+
+// The 2016 lot has the smaller gains and is sold first. Taxes are $2 a share of the 2016 lot and
+// $10 a share of the 2015 lot.
+fn sell<'a>(
+    fund_prices: &'a HashMap<String, f64>,
+    sell_target: f64,
+    target_kind: TargetKind,
+) -> Result<SellSummary<'a>, AccountError> {
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2016-01-04", "VTSAX", 10.0, 80.0),
+    ]);
+    let options = SellOptions {
+        target_kind,
+        ..SellOptions::default()
+    };
+    account.minimum_cap_gains(fund_prices, sell_target, &TaxRates::flat(0.2), date("2018-01-02"), &options)
+}
+
+fn fund_prices() -> HashMap<String, f64> {
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices
+}
+
+fn shares(summary: &SellSummary) -> Vec<f64> {
+    summary.records.iter().map(|s| s.num_shares).collect()
+}
+
+#[test]
+fn gross_target_ignores_taxes() {
+    let fund_prices = fund_prices();
+    let summary = sell(&fund_prices, 1500.0, TargetKind::Gross).unwrap();
+
+    assert_eq!(shares(&summary), vec![10.0, 5.0]);
+    assert_eq!(summary.total_amount, 1500.0);
+    assert_eq!(summary.net_amount, 1410.0);
+}
+
+#[test]
+fn net_target_covers_taxes() {
+    let fund_prices = fund_prices();
+    let summary = sell(&fund_prices, 1500.0, TargetKind::Net).unwrap();
+
+    assert_eq!(shares(&summary), vec![10.0, 6.0]);
+    assert_eq!(summary.total_amount, 1600.0);
+    assert_eq!(summary.net_amount, 1500.0);
+}
+
+#[test]
+fn insufficient_funds_use_the_target_kind() {
+    // everything is worth $2000, or $1860 after taxes
+    let fund_prices = fund_prices();
+    assert!(sell(&fund_prices, 1950.0, TargetKind::Gross).is_ok());
+    assert_eq!(
+        sell(&fund_prices, 1950.0, TargetKind::Net),
+        Err(AccountError::InsufficientFunds {
+            available: 1860.0,
+            target: 1950.0
+        })
+    );
+}
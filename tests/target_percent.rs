@@ -0,0 +1,79 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::process::{Command, Output};
+
+use common::fixture;
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_capgains"))
+        .args(["--account", &fixture("account.csv"), "--fund-prices", &fixture("fund_prices.csv")])
+        .args(["--as-of", "2018-01-02", "--format", "json"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn target_is_a_percentage_of_the_value_of_the_portfolio() {
+    // 25 shares of VTSAX at 100 and 100 shares of VBTLX at 10.5 are worth 3550
+    let output = run(&["--target-percent", "20"]);
+    let json = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(json.contains("\"target\":710,"), "{}", json);
+}
+
+#[test]
+fn target_percent_cannot_be_used_with_target() {
+    let output = run(&["--target-percent", "20", "--target", "500"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("'--target-percent' cannot be used with '--target'"));
+}
+
+#[test]
+fn target_percent_must_be_above_zero_and_at_most_one_hundred() {
+    for percent in &["0", "-5", "101"] {
+        let output = run(&["--target-percent", percent]);
+
+        assert_eq!(output.status.code(), Some(2), "{}", percent);
+        assert!(stderr(&output).contains("must be above 0 and at most 100"), "{}", percent);
+    }
+}
+
+#[test]
+fn percentage_of_a_net_target_is_of_the_value_after_taxes() {
+    let output = run(&["--target-percent", "100", "--tax-rate", "0.2"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let json = String::from_utf8(output.stdout).unwrap();
+
+    assert!(json.contains("\"overshoot\":0"), "{}", json);
+}
+
+#[test]
+fn percentage_is_of_the_records_that_can_be_sold_after_the_sale_fee() {
+    // each of the 4 records pays a fee of 100, leaving 3150 of the 3550
+    let output = run(&["--target-percent", "50", "--sale-fee", "100"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let json = String::from_utf8(output.stdout).unwrap();
+
+    assert!(json.contains("\"target\":1575,"), "{}", json);
+}
+
+#[test]
+fn portfolio_without_records_that_can_be_sold_has_no_percentage() {
+    let output = run(&["--target-percent", "50", "--sale-fee", "10000"]);
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(stderr.contains("'--target-percent'"), "{}", stderr);
+    assert!(stderr.contains("value of the portfolio is 0.00"), "{}", stderr);
+    assert!(!stderr.contains("Sell target must be positive"), "{}", stderr);
+}
@@ -0,0 +1,46 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, SellOptions, TaxBrackets, TaxRates};
+
+use common::{date, record};
+
+// Gains up to $100 are untaxed and gains above are taxed at 50%.
+fn tax_rates(income: f64) -> TaxRates {
+    let mut tax_rates = TaxRates::flat(0.0);
+    tax_rates.brackets = Some(TaxBrackets::new(income, vec![(100.0, 0.5), (0.0, 0.0)]));
+    tax_rates
+}
+
+#[test]
+fn gains_on_top_of_income_use_marginal_rates() {
+    let brackets = TaxBrackets::new(50.0, vec![(0.0, 0.0), (100.0, 0.5)]);
+
+    assert_eq!(brackets.taxes(40.0), 0.0);
+    assert_eq!(brackets.taxes(150.0), 50.0);
+}
+
+#[test]
+fn sale_covers_taxes_in_the_upper_bracket() {
+    // The 2016 lot has $100 of gains, all in the untaxed bracket. Each share of the 2015 lot adds
+    // $20 of gains taxed at 50%, so six shares are needed to net $1500 after $60 of taxes.
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 80.0),
+        record("2016-01-04", "VTSAX", 10.0, 90.0),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let tax_rates = tax_rates(0.0);
+    let summary = account
+        .minimum_cap_gains(&fund_prices, 1500.0, &tax_rates, date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+
+    assert_eq!(summary.records.iter().map(|s| s.num_shares).collect::<Vec<_>>(), vec![10.0, 6.0]);
+    assert_eq!(summary.total_amount, 1600.0);
+    assert_eq!(summary.total_tax, 60.0);
+    assert_eq!(summary.net_amount, 1540.0);
+}
@@ -0,0 +1,97 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, AccountError, SellOptions, TaxRates};
+
+use common::{date, record};
+
+#[test]
+fn per_fund_rates_override_term_rates() {
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2015-01-02", "VWIUX", 10.0, 10.0),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices.insert("VWIUX".to_string(), 15.0);
+
+    let mut tax_rates = TaxRates::flat(0.2);
+    tax_rates.per_fund.insert("VWIUX".to_string(), 0.0);
+
+    let sell_records = account
+        .make_sell_records(&fund_prices, &tax_rates, date("2018-01-02"), &SellOptions::default())
+        .unwrap();
+    let taxes: Vec<f64> = sell_records.iter().map(|s| tax_rates.taxes(s)).collect();
+    assert_eq!(taxes, vec![100.0, 0.0]);
+
+    let net_amounts: Vec<f64> = sell_records.iter().map(|s| s.net_amount).collect();
+    assert_eq!(net_amounts, vec![900.0, 150.0]);
+}
+
+#[test]
+fn parses_flat_and_term_rates() {
+    let flat: TaxRates = "0.15".parse().unwrap();
+    assert_eq!((flat.short_term, flat.long_term), (0.15, 0.15));
+
+    let term: TaxRates = "0.35, 0.15".parse().unwrap();
+    assert_eq!((term.short_term, term.long_term), (0.35, 0.15));
+
+    assert!("0.35,".parse::<TaxRates>().is_err());
+}
+
+#[test]
+fn rates_must_be_between_zero_and_one() {
+    for rate in &["0", "1", "0.35,0.15", "1,0"] {
+        assert!(rate.parse::<TaxRates>().is_ok(), "{}", rate);
+    }
+    for rate in &["-0.01", "1.01", "0.2,1.5", "-0,-0.1"] {
+        assert!(rate.parse::<TaxRates>().is_err(), "{}", rate);
+    }
+
+    let mut tax_rates = TaxRates::flat(0.2);
+    tax_rates.per_fund.insert("VWIUX".to_string(), 3.0);
+    assert_eq!(tax_rates.validate(), Err(AccountError::InvalidTaxRate { rate: 3.0 }));
+}
+
+#[test]
+fn sell_target_must_be_positive() {
+    let account = Account::new(vec![record("2015-01-02", "VTSAX", 10.0, 50.0)]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let tax_rates = TaxRates::flat(0.2);
+    let options = SellOptions::default();
+    let sell = |target| account.minimum_cap_gains(&fund_prices, target, &tax_rates, date("2018-01-02"), &options);
+
+    assert_eq!(sell(0.0), Err(AccountError::InvalidTarget { target: 0.0 }));
+    assert_eq!(sell(-100.0), Err(AccountError::InvalidTarget { target: -100.0 }));
+    assert_eq!(sell(0.01).unwrap().records[0].num_shares, 1.0);
+}
+
+#[test]
+fn minimize_tax_sells_the_lowest_taxes_per_dollar_first() {
+    let account = Account::new(vec![
+        // a ratio of 0.3, taxed at 15%: $0.045 per dollar
+        record("2015-01-02", "VTSAX", 10.0, 70.0),
+        // a ratio of 0.2, taxed at 35%: $0.07 per dollar
+        record("2017-09-01", "VTSAX", 10.0, 80.0),
+    ]);
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    let tax_rates = TaxRates {
+        long_term: 0.15,
+        ..TaxRates::flat(0.35)
+    };
+    let sale_date = date("2018-01-02");
+    let options = SellOptions::default();
+
+    let min_cg = account.minimum_cap_gains(&fund_prices, 900.0, &tax_rates, sale_date, &options).unwrap();
+    let min_tax = account.minimize_tax(&fund_prices, 900.0, &tax_rates, sale_date, &options).unwrap();
+
+    assert_eq!(min_cg.records[0].date_purchased, date("2017-09-01"));
+    assert_eq!(min_tax.records[0].date_purchased, date("2015-01-02"));
+    assert!(min_tax.total_tax < min_cg.total_tax);
+}
@@ -0,0 +1,125 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, AccountError, SellOptions, SellRecord, TaxRates, YearLimit};
+
+use common::{date, record};
+
+// The VBTLX lot has the lowest gains and is sold first, then the VTSAX lots.
+fn account() -> Account {
+    Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2017-03-01", "VTSAX", 10.0, 80.0),
+        record("2016-01-04", "VBTLX", 100.0, 10.0),
+    ])
+}
+
+fn fund_prices() -> HashMap<String, f64> {
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices.insert("VBTLX".to_string(), 10.5);
+    fund_prices
+}
+
+fn limit(cap_gains: f64, next_sale_date: &str) -> YearLimit {
+    YearLimit {
+        cap_gains,
+        next_sale_date: date(next_sale_date),
+    }
+}
+
+fn lots<'a>(records: &[SellRecord<'a>]) -> Vec<(&'a str, f64)> {
+    records.iter().map(|s| (s.fund, s.num_shares)).collect()
+}
+
+#[test]
+fn gains_over_the_limit_are_sold_next_year() {
+    let fund_prices = fund_prices();
+    let split = account()
+        .split_across_years(
+            &fund_prices,
+            2500.0,
+            &limit(250.0, "2018-01-02"),
+            &TaxRates::flat(0.0),
+            date("2017-12-15"),
+            &SellOptions::default(),
+        )
+        .unwrap();
+
+    // $50 of gains from VBTLX leaves room for 10 shares of VTSAX at $20 a share
+    assert_eq!(lots(&split.first.records), vec![("VBTLX", 100.0), ("VTSAX", 10.0)]);
+    assert_eq!(split.first.total_cap_gains, 250.0);
+    assert_eq!(split.first.total_amount, 2050.0);
+
+    let second = split.second.unwrap();
+    assert_eq!(lots(&second.records), vec![("VTSAX", 5.0)]);
+    assert_eq!(second.records[0].sale_date, date("2018-01-02"));
+    assert_eq!(lots(&second.remaining), vec![("VTSAX", 5.0)]);
+}
+
+#[test]
+fn holding_periods_end_on_the_next_sale_date() {
+    let fund_prices = fund_prices();
+    let split = account()
+        .split_across_years(
+            &fund_prices,
+            2500.0,
+            &limit(50.0, "2018-03-02"),
+            &TaxRates::flat(0.0),
+            date("2017-12-15"),
+            &SellOptions::default(),
+        )
+        .unwrap();
+
+    assert_eq!(lots(&split.first.records), vec![("VBTLX", 100.0)]);
+    let second = split.second.unwrap();
+    assert_eq!(lots(&second.records), vec![("VTSAX", 10.0), ("VTSAX", 5.0)]);
+    // short term this year, long term by the next sale date
+    assert_eq!(second.records[0].date_purchased, date("2017-03-01"));
+    assert!(second.records[0].is_long_term);
+    assert_eq!(second.records[0].holding_days, 366);
+}
+
+#[test]
+fn target_reached_within_the_limit_is_sold_this_year() {
+    let fund_prices = fund_prices();
+    let split = account()
+        .split_across_years(
+            &fund_prices,
+            1000.0,
+            &limit(250.0, "2018-01-02"),
+            &TaxRates::flat(0.0),
+            date("2017-12-15"),
+            &SellOptions::default(),
+        )
+        .unwrap();
+
+    assert_eq!(lots(&split.first.records), vec![("VBTLX", 96.0)]);
+    assert!(split.second.is_none());
+    assert_eq!(split.first.remaining.len(), 3);
+}
+
+#[test]
+fn insufficient_funds_count_both_years() {
+    let fund_prices = fund_prices();
+    let result = account().split_across_years(
+        &fund_prices,
+        5000.0,
+        &limit(250.0, "2018-01-02"),
+        &TaxRates::flat(0.0),
+        date("2017-12-15"),
+        &SellOptions::default(),
+    );
+
+    assert_eq!(
+        result,
+        Err(AccountError::InsufficientFunds {
+            available: 3050.0,
+            target: 5000.0,
+        })
+    );
+}
@@ -0,0 +1,51 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::fs;
+
+use capgains::{load_account, load_fund_prices, testutil, Account, InputOptions, SellOptions, TaxRates};
+
+use common::{date, TempFile};
+
+#[test]
+fn the_same_seed_generates_the_same_account() {
+    let (records, fund_prices) = testutil::generate(100, 7);
+    let (again, again_prices) = testutil::generate(100, 7);
+    let (other, _) = testutil::generate(100, 8);
+
+    let fields = |records: &[capgains::Record]| -> Vec<_> {
+        records.iter().map(|r| (r.date, r.fund.clone(), r.num_shares, r.share_price)).collect()
+    };
+    assert_eq!(records.len(), 100);
+    assert_eq!(fields(&records), fields(&again));
+    assert_eq!(fund_prices, again_prices);
+    assert_ne!(fields(&records), fields(&other));
+}
+
+#[test]
+fn generated_files_load_as_an_account() {
+    let (records, fund_prices) = testutil::generate(200, 42);
+    let account_file = TempFile::new("generated-account.csv");
+    let prices_file = TempFile::new("generated-prices.csv");
+    testutil::write_account(fs::File::create(account_file.path()).unwrap(), &records).unwrap();
+    testutil::write_fund_prices(fs::File::create(prices_file.path()).unwrap(), &fund_prices).unwrap();
+
+    let options = InputOptions::default();
+    let account = load_account(account_file.path(), &options);
+    let loaded_prices = load_fund_prices(prices_file.path(), &options);
+
+    let account = account.unwrap();
+    assert_eq!(loaded_prices.unwrap(), fund_prices);
+    assert_eq!(account.validate_amounts(0.01), Ok(()));
+
+    let sale_date = date("2018-01-02");
+    let sell_options = SellOptions::default();
+    let tax_rates = TaxRates::flat(0.2);
+    let loaded = account.make_sell_records(&fund_prices, &tax_rates, sale_date, &sell_options).unwrap();
+    let generated = Account::new(records)
+        .make_sell_records(&fund_prices, &tax_rates, sale_date, &sell_options)
+        .unwrap();
+    assert_eq!(loaded, generated);
+}
@@ -0,0 +1,39 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use capgains::money::Cents;
+use capgains::{Account, AccountError};
+
+use common::{record, transaction};
+
+#[test]
+fn amounts_within_tolerance_pass() {
+    let mut buy = record("2015-01-02", "VTSAX", 3.0, 33.333);
+    buy.amount = Cents::from_dollars(100.0);
+    let account = Account::new(vec![
+        buy,
+        transaction("2016-01-04", "VTSAX", "Sell", -1.0, 40.0),
+        transaction("2016-06-30", "VTSAX", "Dividend", 0.0, 45.0),
+    ]);
+
+    assert!(account.validate_amounts(0.01).is_ok());
+}
+
+#[test]
+fn mismatched_amounts_are_listed() {
+    let mut buy = record("2015-01-02", "VTSAX", 10.0, 50.0);
+    buy.amount = Cents::from_dollars(5000.0);
+    let account = Account::new(vec![buy, record("2016-01-04", "VTSAX", 10.0, 60.0)]);
+
+    let err = account.validate_amounts(0.01).unwrap_err();
+    assert!(err.to_string().contains("1 record(s)"));
+    match err {
+        AccountError::MismatchedAmounts(records) => {
+            assert_eq!(records.len(), 1);
+            assert!(records[0].contains("2015-01-02"));
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+}
@@ -0,0 +1,103 @@
+extern crate capgains;
+extern crate chrono;
+
+mod common;
+
+use std::collections::HashMap;
+
+use capgains::{Account, SellOptions, TaxRates};
+
+use common::{date, record, transaction};
+
+fn fund_prices() -> HashMap<String, f64> {
+    let mut fund_prices = HashMap::new();
+    fund_prices.insert("VTSAX".to_string(), 100.0);
+    fund_prices.insert("VBTLX".to_string(), 10.0);
+    fund_prices
+}
+
+#[test]
+fn loss_with_recent_acquisition_is_flagged() {
+    // The 2017 lot is sold at a loss and shares were reinvested 10 days before the sale date.
+    let account = Account::new(vec![
+        record("2015-01-02", "VTSAX", 10.0, 50.0),
+        record("2017-06-01", "VTSAX", 10.0, 120.0),
+        transaction("2017-12-23", "VTSAX", "Reinvestment", 1.0, 100.0),
+    ]);
+    let fund_prices = fund_prices();
+    let sale_date = date("2018-01-02");
+    let mut result = account
+        .minimum_cap_gains(&fund_prices, 1500.0, &TaxRates::flat(0.0), sale_date, &SellOptions::default())
+        .unwrap()
+        .records;
+    account.flag_wash_sales(&mut result, sale_date);
+
+    assert_eq!(result[0].date_purchased, date("2017-06-01"));
+    assert_eq!(result[0].wash_sale, Some(date("2017-12-23")));
+    assert!(result[1..].iter().all(|s| s.cap_gains.to_dollars() >= 0.0 && s.wash_sale.is_none()));
+}
+
+#[test]
+fn acquisitions_outside_window_are_ignored() {
+    let account = Account::new(vec![
+        record("2017-06-01", "VTSAX", 10.0, 120.0),
+        record("2017-11-20", "VTSAX", 1.0, 100.0),
+        record("2017-12-23", "VBTLX", 1.0, 10.0),
+    ]);
+    let fund_prices = fund_prices();
+    let sale_date = date("2018-01-02");
+    let mut result = account
+        .fifo_sell(&fund_prices, 500.0, &TaxRates::flat(0.0), sale_date, &SellOptions::default())
+        .unwrap()
+        .records;
+    account.flag_wash_sales(&mut result, sale_date);
+
+    assert_eq!(result.len(), 1);
+    assert!(result[0].cap_gains.to_dollars() < 0.0);
+    assert_eq!(result[0].wash_sale, None);
+}
+
+#[test]
+fn disallowed_loss_moves_to_the_replacement_shares() {
+    // 10 shares are sold at a loss of 200 and bought back two weeks later.
+    let records = vec![
+        record("2017-01-03", "VTSAX", 10.0, 100.0),
+        transaction("2017-06-01", "VTSAX", "Sell", 10.0, 80.0),
+        record("2017-06-15", "VTSAX", 10.0, 85.0),
+    ];
+    let adjusted = Account::new(records.clone()).with_wash_sale_adjustment(true);
+    let fund_prices = fund_prices();
+    let sell = |account: &Account| {
+        account
+            .minimum_cap_gains(&fund_prices, 1000.0, &TaxRates::flat(0.0), date("2018-03-01"), &SellOptions::default())
+            .unwrap()
+            .records
+    };
+
+    let unadjusted = sell(&Account::new(records));
+    assert_eq!((unadjusted[0].cap_gains.to_dollars(), unadjusted[0].is_long_term), (150.0, false));
+
+    let holdings = adjusted.holdings().unwrap();
+    assert_eq!(holdings.len(), 1);
+    assert_eq!((holdings[0].share_price, holdings[0].amount.to_dollars()), (105.0, 1050.0));
+    // held for 149 days before the sale, which carry over
+    assert_eq!(holdings[0].date, date("2017-01-17"));
+    let result = sell(&adjusted);
+    assert_eq!((result[0].cap_gains.to_dollars(), result[0].is_long_term), (-50.0, true));
+}
+
+#[test]
+fn only_the_loss_on_the_replaced_shares_is_disallowed() {
+    // 4 of the shares sold at a loss are replaced by shares bought before the sell, which are not
+    // sold by it, as the older lot is sold first. The shares bought two months later replace none.
+    let account = Account::new(vec![
+        record("2017-01-03", "VTSAX", 10.0, 100.0),
+        record("2017-05-20", "VTSAX", 4.0, 82.0),
+        record("2017-08-01", "VTSAX", 2.0, 90.0),
+        transaction("2017-06-01", "VTSAX", "Sell", 10.0, 80.0),
+    ])
+    .with_wash_sale_adjustment(true);
+
+    let holdings: Vec<_> = account.holdings().unwrap().iter().map(|r| (r.date, r.num_shares, r.share_price)).collect();
+    assert_eq!(holdings, vec![(date("2016-12-22"), 4.0, 102.0), (date("2017-08-01"), 2.0, 90.0)]);
+}